@@ -0,0 +1,212 @@
+use crate::block::Block;
+use crate::map::{MapError, MapFile, MapMeta};
+use crate::palette::TileType;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Characters handed out to blocks that don't have an obvious glyph of their own.
+const SPARE_GLYPHS: &str =
+    "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0!$%&+,-/;<>?@|";
+
+/// A map drawn as a grid of characters, with a legend saying which block each character is.
+///
+/// ```text
+/// (
+///     legend: { '#': Barrier, '~': Water, '.': Grass, 'x': Object('x'), '1': Sign("Hello!") },
+///     grid: [
+///         "#####",
+///         "#.1x#",
+///         "#~~~#",
+///         "#####",
+///     ],
+/// )
+/// ```
+///
+/// The first row of the grid is `y = origin.1`, the first column `x = origin.0`.
+/// Spaces are left without a tile.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AsciiMap {
+    #[serde(default)]
+    pub meta: MapMeta,
+    #[serde(default)]
+    pub palette: Vec<TileType>,
+    #[serde(default)]
+    pub origin: (i32, i32),
+    pub legend: BTreeMap<char, Block>,
+    pub grid: Vec<String>,
+}
+
+impl AsciiMap {
+    pub fn into_map_file(self) -> Result<MapFile, MapError> {
+        let mut tiles = HashMap::new();
+        for (row, line) in self.grid.iter().enumerate() {
+            for (column, glyph) in line.chars().enumerate() {
+                if glyph == ' ' {
+                    continue;
+                }
+                let x = self.origin.0 + column as i32;
+                let y = self.origin.1 + row as i32;
+                match self.legend.get(&glyph) {
+                    Some(block) => {
+                        tiles.insert((x, y), block.clone());
+                    }
+                    None => return Err(MapError::UnknownGlyph { glyph, x, y }),
+                }
+            }
+        }
+        Ok(MapFile {
+            meta: self.meta,
+            palette: self.palette,
+            tiles,
+        })
+    }
+
+    /// Draws a map as a grid, making up a legend for every block it contains. Fails if there
+    /// are more kinds of block than characters to go around.
+    pub fn from_map_file(map: MapFile) -> Result<AsciiMap, MapError> {
+        let ((min_x, min_y), (max_x, max_y)) = match map.bounds() {
+            Some(bounds) => (bounds.min, bounds.max),
+            None => {
+                return Ok(AsciiMap {
+                    meta: map.meta,
+                    palette: map.palette,
+                    origin: (0, 0),
+                    legend: BTreeMap::new(),
+                    grid: vec![],
+                })
+            }
+        };
+
+        let mut glyphs: HashMap<&Block, char> = HashMap::new();
+        let mut used: HashSet<char> = HashSet::from([' ']);
+        let mut spare = SPARE_GLYPHS.chars();
+        let mut blocks: Vec<&Block> = map.tiles.values().collect();
+        // Blocks with a preferred glyph go first, so they are less likely to lose it.
+        blocks.sort_by_key(|b| preferred_glyph(b, &map.palette).is_none());
+        for block in blocks {
+            if glyphs.contains_key(block) {
+                continue;
+            }
+            let glyph = match preferred_glyph(block, &map.palette) {
+                Some(c) if !used.contains(&c) => c,
+                _ => match spare.find(|c| !used.contains(c)) {
+                    Some(c) => c,
+                    None => {
+                        let kinds = map.tiles.values().collect::<HashSet<_>>().len();
+                        return Err(MapError::TooManyBlocks(kinds));
+                    }
+                },
+            };
+            used.insert(glyph);
+            glyphs.insert(block, glyph);
+        }
+
+        let grid = (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| match map.tiles.get(&(x, y)) {
+                        Some(block) => glyphs[block],
+                        None => ' ',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        let legend = glyphs.into_iter().map(|(b, c)| (c, b.clone())).collect();
+
+        Ok(AsciiMap {
+            meta: map.meta,
+            palette: map.palette,
+            origin: (min_x, min_y),
+            legend,
+            grid,
+        })
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, PrettyConfig::default())
+    }
+}
+
+fn preferred_glyph(block: &Block, palette: &[TileType]) -> Option<char> {
+    match block {
+        Block::Grass => Some('.'),
+        Block::Sand => Some(':'),
+        Block::Rock => Some('^'),
+        Block::Cinderblock => Some('='),
+        Block::Flowerbush => Some('*'),
+        Block::Barrier => Some('#'),
+        Block::Water => Some('~'),
+        Block::Empty => Some('_'),
+        Block::Object(c) => Some(*c),
+        Block::Checkpoint => Some('⚑'),
+        Block::Custom(name) => palette
+            .iter()
+            .find(|t| &t.name == name)
+            .map(|t| t.glyph)
+            .filter(|c| *c != ' '),
+        Block::Sign(_) | Block::Npc(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ascii::AsciiMap;
+    use crate::block::Block;
+    use crate::map::{MapError, MapFile};
+
+    #[test]
+    fn grid_to_tiles() {
+        let source = r##"(
+            origin: (1, 2),
+            legend: { '#': Barrier, '~': Water, 'x': Object('x'), '1': Sign("Hi") },
+            grid: [
+                "#1x",
+                "~ #",
+            ],
+        )"##;
+        let map = MapFile::parse(source).unwrap();
+        assert_eq!(5, map.tiles.len());
+        assert_eq!(Some(&Block::Barrier), map.tiles.get(&(1, 2)));
+        assert_eq!(Some(&Block::Sign("Hi".to_string())), map.tiles.get(&(2, 2)));
+        assert_eq!(Some(&Block::Object('x')), map.tiles.get(&(3, 2)));
+        assert_eq!(Some(&Block::Water), map.tiles.get(&(1, 3)));
+        assert_eq!(None, map.tiles.get(&(2, 3)));
+    }
+
+    #[test]
+    fn unknown_glyph() {
+        let source = r##"(legend: { '#': Barrier }, grid: ["#?"])"##;
+        assert!(matches!(
+            MapFile::parse(source),
+            Err(MapError::UnknownGlyph {
+                glyph: '?',
+                x: 1,
+                y: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let map = MapFile::parse(include_str!("../../maps/testing_game.ron")).unwrap();
+        let tiles = map.tiles.clone();
+        let ascii = AsciiMap::from_map_file(map).unwrap();
+        assert_eq!(Some(&Block::Barrier), ascii.legend.get(&'#'));
+        assert_eq!(tiles, ascii.into_map_file().unwrap().tiles);
+    }
+
+    #[test]
+    fn runs_out_of_glyphs() {
+        let signs = (0..200)
+            .map(|i| format!("({}, 0): Sign(\"{}\")", i, i))
+            .collect::<Vec<_>>();
+        let map = MapFile::parse(&format!("{{ {} }}", signs.join(", "))).unwrap();
+        assert!(matches!(
+            AsciiMap::from_map_file(map),
+            Err(MapError::TooManyBlocks(200))
+        ));
+    }
+}
@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use termgame::{GameColor, GameStyle, StyledCharacter};
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Block {
     Grass,
     Sand,
@@ -11,30 +11,46 @@ pub enum Block {
     Water,
     Sign(String),
     Object(char),
+    /// Saves how things are when stepped on, to come back to after dying if the map's rules
+    /// give the player lives.
+    Checkpoint,
+    /// Someone to talk to, who stands in the way. What they say is in `<map>.npcs.ron`.
+    Npc(String),
+    Custom(String),
     Empty,
 }
 
+impl Block {
+    /// The colour a block of terrain is drawn in, or `None` for signs, objects, checkpoints
+    /// and NPCs, which are drawn as a character instead.
+    pub fn color(&self) -> Option<GameColor> {
+        match self {
+            Block::Grass => Some(GameColor::Green),
+            Block::Sand => Some(GameColor::Yellow),
+            Block::Rock => Some(GameColor::Gray),
+            Block::Cinderblock => Some(GameColor::LightRed),
+            Block::Flowerbush => Some(GameColor::Magenta),
+            Block::Barrier => Some(GameColor::White),
+            Block::Water => Some(GameColor::Blue),
+            Block::Empty => Some(GameColor::Black),
+            Block::Sign(_)
+            | Block::Object(_)
+            | Block::Checkpoint
+            | Block::Npc(_)
+            | Block::Custom(_) => None,
+        }
+    }
+}
+
 impl From<&Block> for StyledCharacter {
     fn from(block: &Block) -> Self {
-        let styled = StyledCharacter::new(' ');
         match block {
-            Block::Grass => styled.style(GameStyle::new().background_color(Some(GameColor::Green))),
-            Block::Sand => styled.style(GameStyle::new().background_color(Some(GameColor::Yellow))),
-            Block::Rock => styled.style(GameStyle::new().background_color(Some(GameColor::Gray))),
-            Block::Cinderblock => {
-                styled.style(GameStyle::new().background_color(Some(GameColor::LightRed)))
-            }
-            Block::Flowerbush => {
-                styled.style(GameStyle::new().background_color(Some(GameColor::Magenta)))
-            }
-
-            Block::Barrier => {
-                styled.style(GameStyle::new().background_color(Some(GameColor::White)))
-            }
-            Block::Water => styled.style(GameStyle::new().background_color(Some(GameColor::Blue))),
             Block::Sign(_) => StyledCharacter::new('💬'),
             Block::Object(char) => StyledCharacter::new(*char),
-            Block::Empty => styled.style(GameStyle::new().background_color(Some(GameColor::Black))),
+            Block::Checkpoint => StyledCharacter::new('⚑'),
+            Block::Npc(_) => StyledCharacter::new('☺'),
+            Block::Custom(_) => StyledCharacter::new('?'),
+            _ => StyledCharacter::new(' ').style(GameStyle::new().background_color(block.color())),
         }
     }
 }
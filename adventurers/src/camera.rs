@@ -0,0 +1,180 @@
+use crate::viewport::view_size;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use termgame::{Game, ViewportLocation};
+
+/// How the screen follows the player around the map.
+///
+/// Maps can pick one in their header, e.g. `camera: DeadZone(width: 30, height: 10)`,
+/// and `--camera` overrides it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// Scroll just enough to keep the player on screen.
+    #[default]
+    Edge,
+    /// Keep the player in the middle of the screen.
+    Centered,
+    /// Only scroll when the player leaves a box of this size in the middle of the screen.
+    DeadZone { width: i32, height: i32 },
+    /// Jump a whole screen at a time when the player walks off the edge.
+    PageFlip,
+}
+
+impl FromStr for CameraMode {
+    type Err = String;
+
+    /// Parses `edge`, `centered`, `page`, `dead-zone` or `dead-zone:WxH`.
+    fn from_str(arg: &str) -> Result<Self, Self::Err> {
+        match arg.split_once(':') {
+            None if arg == "edge" => Ok(CameraMode::Edge),
+            None if arg == "centered" => Ok(CameraMode::Centered),
+            None if arg == "page" => Ok(CameraMode::PageFlip),
+            None if arg == "dead-zone" => Ok(CameraMode::DeadZone {
+                width: 20,
+                height: 8,
+            }),
+            Some(("dead-zone", size)) => match size.split_once('x') {
+                Some((width, height)) => match (width.parse(), height.parse()) {
+                    (Ok(width), Ok(height)) => Ok(CameraMode::DeadZone { width, height }),
+                    _ => Err(format!("expected a size like `20x8`, found `{}`", size)),
+                },
+                None => Err(format!("expected a size like `20x8`, found `{}`", size)),
+            },
+            _ => Err(format!(
+                "expected edge, centered, page, dead-zone or dead-zone:WxH, found `{}`",
+                arg
+            )),
+        }
+    }
+}
+
+/// Moves the viewport to follow a position, the way its [`CameraMode`] says.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Camera {
+    pub mode: CameraMode,
+}
+
+impl Camera {
+    pub fn new(mode: CameraMode) -> Self {
+        Camera { mode }
+    }
+
+    pub fn follow(&self, game: &mut Game, (x, y): (i32, i32)) {
+        let (width, height) = view_size(game);
+        let viewport = game.get_viewport();
+        let (vx, vy) = match self.mode {
+            // A tile is kept free at the right and bottom edges, as it always was, unless that
+            // would leave no room for the player.
+            CameraMode::Edge => (
+                edge(viewport.x, x, 0, (width - 1).max(1)),
+                edge(viewport.y, y, 0, (height - 1).max(1)),
+            ),
+            CameraMode::Centered => (x - width / 2, y - height / 2),
+            CameraMode::DeadZone {
+                width: zone_width,
+                height: zone_height,
+            } => {
+                let zone_width = zone_width.clamp(1, (width - 1).max(1));
+                let zone_height = zone_height.clamp(1, (height - 1).max(1));
+                let left = (width - zone_width) / 2;
+                let top = (height - zone_height) / 2;
+                (
+                    edge(viewport.x, x, left, left + zone_width),
+                    edge(viewport.y, y, top, top + zone_height),
+                )
+            }
+            CameraMode::PageFlip => (page(viewport.x, x, width), page(viewport.y, y, height)),
+        };
+        game.set_viewport(ViewportLocation { x: vx, y: vy });
+    }
+}
+
+/// Scrolls a viewport starting at `start` just enough to put `position` between `low` and
+/// `high` tiles (exclusive) into it.
+fn edge(start: i32, position: i32, low: i32, high: i32) -> i32 {
+    if position < start + low {
+        position - low
+    } else if position >= start + high {
+        position - high + 1
+    } else {
+        start
+    }
+}
+
+/// The start of the page of `size` tiles that `position` is on, if it has left the current one.
+fn page(start: i32, position: i32, size: i32) -> i32 {
+    if position < start || position >= start + size {
+        position.div_euclid(size) * size
+    } else {
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{Camera, CameraMode};
+    use termgame::{CharChunkMap, Game, Message};
+
+    fn viewport_after(mode: CameraMode, steps: &[(i32, i32)]) -> (i32, i32) {
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+        let camera = Camera::new(mode);
+        for step in steps {
+            camera.follow(&mut game, *step);
+        }
+        let viewport = game.get_viewport();
+        (viewport.x, viewport.y)
+    }
+
+    #[test]
+    fn modes() {
+        // The screen shows 78x21 tiles, above the status bar.
+        let steps = [(10, 10), (77, 21), (80, 10)];
+        assert_eq!((4, 2), viewport_after(CameraMode::Edge, &steps));
+        assert_eq!(
+            (80 - 39, 10 - 10),
+            viewport_after(CameraMode::Centered, &steps)
+        );
+        assert_eq!((78, 0), viewport_after(CameraMode::PageFlip, &steps));
+        let zone = CameraMode::DeadZone {
+            width: 20,
+            height: 8,
+        };
+        // The zone starts 29 tiles from the left, 6 from the top.
+        assert_eq!((80 - 48, 10 - 6), viewport_after(zone, &steps));
+    }
+
+    #[test]
+    fn no_room_left() {
+        // A message this tall leaves a single row of the map, which still shows the player.
+        let viewport = |mode| {
+            let mut screen = CharChunkMap::new();
+            let mut game = Game::new(&mut screen);
+            game.set_message(Some(Message::new("\n".repeat(21))));
+            Camera::new(mode).follow(&mut game, (5, 5));
+            let viewport = game.get_viewport();
+            (viewport.x, viewport.y)
+        };
+        assert_eq!((0, 5), viewport(CameraMode::Edge));
+        assert_eq!((5 - 39, 5), viewport(CameraMode::Centered));
+        let zone = CameraMode::DeadZone {
+            width: 20,
+            height: 8,
+        };
+        assert_eq!((5 - 29, 5), viewport(zone));
+        assert_eq!((0, 5), viewport(CameraMode::PageFlip));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Ok(CameraMode::Centered), "centered".parse());
+        assert_eq!(
+            Ok(CameraMode::DeadZone {
+                width: 30,
+                height: 10
+            }),
+            "dead-zone:30x10".parse()
+        );
+        assert!("dead-zone:big".parse::<CameraMode>().is_err());
+    }
+}
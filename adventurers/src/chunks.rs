@@ -0,0 +1,432 @@
+use crate::block::Block;
+use crate::creatures::Creature;
+use crate::dialogue::Npcs;
+use crate::items::Items;
+use crate::map::{Bounds, MapError, MapFile, MapMeta};
+use crate::palette::TileType;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Chunks are squares of this many tiles a side.
+pub const CHUNK_SIZE: i32 = 32;
+/// Chunks read from disk beyond this many are dropped again, least recently used first.
+const MAX_CHUNKS: usize = 64;
+
+/// The chunk holding a position.
+pub fn chunk_of((x, y): (i32, i32)) -> (i32, i32) {
+    (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+}
+
+/// Every position in a chunk.
+pub fn chunk_positions((cx, cy): (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    (0..CHUNK_SIZE * CHUNK_SIZE).map(move |i| {
+        (
+            cx * CHUNK_SIZE + i % CHUNK_SIZE,
+            cy * CHUNK_SIZE + i / CHUNK_SIZE,
+        )
+    })
+}
+
+fn index((x, y): (i32, i32)) -> usize {
+    (x.rem_euclid(CHUNK_SIZE) + y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE) as usize
+}
+
+#[derive(Clone)]
+struct Chunk {
+    tiles: Vec<Option<Block>>,
+    last_used: u64,
+}
+
+impl Chunk {
+    fn empty() -> Self {
+        Chunk {
+            tiles: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            last_used: 0,
+        }
+    }
+}
+
+/// The tiles of a map, split into fixed-size chunks.
+///
+/// Chunks of a world saved with [`save_world`] are read from disk as they come into view, and
+/// dropped again once too many are loaded, so memory stays bounded however big the world is.
+/// Only the tiles changed since, e.g. by picking up an object, are kept for chunks that were
+/// dropped. Maps read from a single file have every chunk in memory from the start.
+#[derive(Clone)]
+pub struct ChunkedMap {
+    chunks: HashMap<(i32, i32), Chunk>,
+    /// The world directory chunks are read from, if any.
+    source: Option<PathBuf>,
+    /// The chunks on screen.
+    active: HashSet<(i32, i32)>,
+    /// Every tile changed in a chunk read from disk, by chunk, to change again when the chunk
+    /// is read back after being dropped.
+    edits: HashMap<(i32, i32), HashMap<usize, Option<Block>>>,
+    clock: u64,
+    /// How many tiles the whole map had when it was opened.
+    size: usize,
+}
+
+impl ChunkedMap {
+    pub fn new(tiles: HashMap<(i32, i32), Block>) -> Self {
+        let mut map = ChunkedMap {
+            chunks: HashMap::new(),
+            source: None,
+            active: HashSet::new(),
+            edits: HashMap::new(),
+            clock: 0,
+            size: tiles.len(),
+        };
+        for (position, block) in tiles {
+            map.chunks
+                .entry(chunk_of(position))
+                .or_insert_with(Chunk::empty)
+                .tiles[index(position)] = Some(block);
+        }
+        map
+    }
+
+    /// The tile at `position`, if its chunk is loaded and it has one.
+    pub fn get(&self, position: &(i32, i32)) -> Option<&Block> {
+        self.chunks
+            .get(&chunk_of(*position))
+            .and_then(|chunk| chunk.tiles[index(*position)].as_ref())
+    }
+
+    pub fn insert(&mut self, position: (i32, i32), block: Block) -> Option<Block> {
+        self.remember(position, Some(block.clone()));
+        let chunk = self
+            .chunks
+            .entry(chunk_of(position))
+            .or_insert_with(Chunk::empty);
+        chunk.tiles[index(position)].replace(block)
+    }
+
+    pub fn remove(&mut self, position: &(i32, i32)) -> Option<Block> {
+        let chunk = self.chunks.get_mut(&chunk_of(*position))?;
+        let removed = chunk.tiles[index(*position)].take();
+        self.remember(*position, None);
+        removed
+    }
+
+    /// Keeps a change to a world read from disk, which outlives the chunk it is in.
+    fn remember(&mut self, position: (i32, i32), block: Option<Block>) {
+        if self.source.is_some() {
+            self.edits
+                .entry(chunk_of(position))
+                .or_default()
+                .insert(index(position), block);
+        }
+    }
+
+    /// Every tile of a loaded chunk.
+    pub fn chunk_tiles(&self, chunk: (i32, i32)) -> impl Iterator<Item = ((i32, i32), &Block)> {
+        let tiles = self.chunks.get(&chunk).map(|c| &c.tiles);
+        chunk_positions(chunk)
+            .zip(tiles.into_iter().flatten())
+            .filter_map(|(position, block)| block.as_ref().map(|b| (position, b)))
+    }
+
+    /// Every tile of every loaded chunk.
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &Block)> {
+        self.chunks
+            .keys()
+            .flat_map(|chunk| self.chunk_tiles(*chunk))
+    }
+
+    /// Makes sure every chunk overlapping `area` is loaded, reading them from disk if needed.
+    ///
+    /// A chunk that can't be read is left empty, and the error returned.
+    pub fn load(&mut self, area: Bounds) -> Option<MapError> {
+        self.clock += 1;
+        let (min_x, min_y) = chunk_of(area.min);
+        let (max_x, max_y) = chunk_of(area.max);
+        self.active = (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .collect();
+
+        let mut error = None;
+        for chunk in &self.active {
+            if !self.chunks.contains_key(chunk) {
+                let mut loaded = match &self.source {
+                    Some(dir) => read_chunk(dir, *chunk).unwrap_or_else(|err| {
+                        error = Some(err);
+                        Chunk::empty()
+                    }),
+                    None => Chunk::empty(),
+                };
+                for (i, block) in self.edits.get(chunk).into_iter().flatten() {
+                    loaded.tiles[*i] = block.clone();
+                }
+                self.chunks.insert(*chunk, loaded);
+            }
+            self.chunks.get_mut(chunk).unwrap().last_used = self.clock;
+        }
+        self.evict();
+        error
+    }
+
+    /// Drops the least recently used chunks off screen, until few enough are left.
+    fn evict(&mut self) {
+        if self.source.is_none() {
+            return;
+        }
+        while self.chunks.len() > MAX_CHUNKS {
+            let oldest = self
+                .chunks
+                .iter()
+                .filter(|(key, _)| !self.active.contains(key))
+                .min_by_key(|(key, chunk)| (chunk.last_used, **key))
+                .map(|(key, _)| *key);
+            match oldest {
+                Some(key) => self.chunks.remove(&key),
+                None => return,
+            };
+        }
+    }
+
+    /// How many chunks are in memory.
+    pub fn loaded(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The smallest area holding every loaded tile.
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.iter()
+            .map(|(position, _)| position)
+            .fold(None, |bounds, (x, y)| {
+                Some(match bounds {
+                    None => Bounds {
+                        min: (x, y),
+                        max: (x, y),
+                    },
+                    Some(Bounds { min, max }) => Bounds {
+                        min: (min.0.min(x), min.1.min(y)),
+                        max: (max.0.max(x), max.1.max(y)),
+                    },
+                })
+            })
+    }
+
+    /// How many tiles the whole map had when it was opened, loaded or not.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// A map to be played: its header, custom tiles and chunked tiles.
+pub struct World {
+    pub meta: MapMeta,
+    pub palette: Vec<TileType>,
+    pub map: ChunkedMap,
+    pub npcs: Npcs,
+    pub creatures: Vec<Creature>,
+    pub items: Items,
+}
+
+impl From<MapFile> for World {
+    fn from(map: MapFile) -> Self {
+        World {
+            meta: map.meta,
+            palette: map.palette,
+            map: ChunkedMap::new(map.tiles),
+            npcs: Npcs::new(),
+            creatures: vec![],
+            items: Items::new(),
+        }
+    }
+}
+
+/// `world.ron` in a world directory.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    #[serde(default)]
+    meta: MapMeta,
+    #[serde(default)]
+    palette: Vec<TileType>,
+    /// How many tiles there are across all the chunks.
+    #[serde(default)]
+    tiles: usize,
+}
+
+fn chunk_path(dir: &Path, (cx, cy): (i32, i32)) -> PathBuf {
+    dir.join("chunks").join(format!("{}_{}.ron", cx, cy))
+}
+
+/// The chunk a file in `chunks/` holds, the other way round from [`chunk_path`].
+fn chunk_named(name: &str) -> Option<(i32, i32)> {
+    let (x, y) = name.strip_suffix(".ron")?.split_once('_')?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+
+fn read_chunk(dir: &Path, chunk: (i32, i32)) -> Result<Chunk, MapError> {
+    let path = chunk_path(dir, chunk);
+    if !path.exists() {
+        return Ok(Chunk::empty());
+    }
+    let source = std::fs::read_to_string(&path)
+        .map_err(|err| MapError::Chunk(format!("{}: {}", path.display(), err)))?;
+    let tiles = MapFile::parse(&source)
+        .map_err(|err| MapError::Chunk(format!("{}:{}", path.display(), err)))?
+        .tiles;
+    let mut loaded = Chunk::empty();
+    for (position, block) in tiles {
+        if chunk_of(position) != chunk {
+            return Err(MapError::Chunk(format!(
+                "{}: tile {:?} belongs in another chunk",
+                path.display(),
+                position
+            )));
+        }
+        loaded.tiles[index(position)] = Some(block);
+    }
+    Ok(loaded)
+}
+
+impl World {
+    /// Opens a world directory written by [`save_world`]. Chunks are only read once the
+    /// player comes near them.
+    pub fn open(dir: &Path) -> Result<World, MapError> {
+        let path = dir.join("world.ron");
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| MapError::Chunk(format!("{}: {}", path.display(), err)))?;
+        let header: Header = ron::from_str(&source)
+            .map_err(|err| MapError::Chunk(format!("{}:{}", path.display(), err)))?;
+        let mut map = ChunkedMap::new(HashMap::new());
+        map.source = Some(dir.to_path_buf());
+        map.size = header.tiles;
+        Ok(World {
+            meta: header.meta,
+            palette: header.palette,
+            map,
+            npcs: Npcs::new(),
+            creatures: vec![],
+            items: Items::new(),
+        })
+    }
+}
+
+/// Reads every chunk of a world directory into a single map, for the commands that need
+/// all of it at once.
+pub fn load_world(dir: &Path) -> Result<MapFile, MapError> {
+    let World { meta, palette, .. } = World::open(dir)?;
+    let mut tiles = HashMap::new();
+    let chunks = std::fs::read_dir(dir.join("chunks"))
+        .map_err(|err| MapError::Chunk(format!("{}: {}", dir.display(), err)))?;
+    for entry in chunks.flatten() {
+        let chunk = chunk_named(&entry.file_name().to_string_lossy());
+        if let Some(chunk) = chunk {
+            let loaded = read_chunk(dir, chunk)?;
+            tiles.extend(
+                chunk_positions(chunk)
+                    .zip(loaded.tiles)
+                    .filter_map(|(position, block)| Some((position, block?))),
+            );
+        }
+    }
+    Ok(MapFile {
+        meta,
+        palette,
+        tiles,
+    })
+}
+
+/// Writes `map` as a world directory: a `world.ron` header, and a `chunks/x_y.ron` file of
+/// tiles for every chunk that has any.
+pub fn save_world(map: &MapFile, dir: &Path) -> std::io::Result<()> {
+    let to_io = |err: ron::Error| std::io::Error::other(err.to_string());
+    std::fs::create_dir_all(dir.join("chunks"))?;
+    let header = Header {
+        meta: map.meta.clone(),
+        palette: map.palette.clone(),
+        tiles: map.tiles.len(),
+    };
+    let header = ron::ser::to_string_pretty(&header, Default::default()).map_err(to_io)?;
+    std::fs::write(dir.join("world.ron"), header)?;
+
+    let mut chunks: HashMap<_, BTreeMap<_, _>> = HashMap::new();
+    for (position, block) in &map.tiles {
+        chunks
+            .entry(chunk_of(*position))
+            .or_default()
+            .insert(position, block);
+    }
+    // Chunks left without tiles would otherwise be read back from an earlier save.
+    for entry in std::fs::read_dir(dir.join("chunks"))?.flatten() {
+        let chunk = chunk_named(&entry.file_name().to_string_lossy());
+        if chunk.is_some_and(|chunk| !chunks.contains_key(&chunk)) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    for (chunk, tiles) in chunks {
+        std::fs::write(
+            chunk_path(dir, chunk),
+            ron::to_string(&tiles).map_err(to_io)?,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::chunks::{chunk_of, load_world, save_world, ChunkedMap, World, CHUNK_SIZE};
+    use crate::generator::{generate, GeneratorOptions};
+    use crate::map::Bounds;
+    use std::collections::HashMap;
+
+    #[test]
+    fn negative_coordinates() {
+        assert_eq!((-1, -1), chunk_of((-1, -1)));
+        assert_eq!((0, -1), chunk_of((CHUNK_SIZE - 1, -CHUNK_SIZE)));
+        let mut map = ChunkedMap::new(HashMap::from([((-1, -1), Block::Sand)]));
+        assert_eq!(Some(&Block::Sand), map.get(&(-1, -1)));
+        assert_eq!(None, map.get(&(CHUNK_SIZE - 1, -1)));
+        assert_eq!(Some(Block::Sand), map.remove(&(-1, -1)));
+        assert_eq!(None, map.get(&(-1, -1)));
+    }
+
+    #[test]
+    fn world_is_loaded_lazily() {
+        let dir = std::env::temp_dir().join(format!("adventurers-world-{}", std::process::id()));
+        let map = generate(&GeneratorOptions {
+            seed: 7,
+            width: 352,
+            height: 224,
+            ..GeneratorOptions::default()
+        });
+        save_world(&map, &dir).unwrap();
+
+        let mut world = World::open(&dir).unwrap();
+        assert_eq!(0, world.map.loaded());
+        let view = |x, y| Bounds {
+            min: (x - 40, y - 12),
+            max: (x + 40, y + 12),
+        };
+        assert!(world.map.load(view(2, 2)).is_none());
+        assert_eq!(8, world.map.loaded());
+        assert_eq!(map.tiles.get(&(2, 2)), world.map.get(&(2, 2)));
+
+        for x in (0..352).step_by(CHUNK_SIZE as usize) {
+            for y in (0..224).step_by(CHUNK_SIZE as usize) {
+                world.map.load(view(x, y));
+                assert!(world.map.loaded() <= 64);
+                assert_eq!(map.tiles.get(&(x, y)), world.map.get(&(x, y)));
+                // Changed chunks can be dropped too, and keep their changes.
+                world.map.insert((x + 1, y + 1), Block::Object('x'));
+            }
+        }
+        world.map.load(view(2, 2));
+        assert_eq!(Some(&Block::Object('x')), world.map.get(&(1, 1)));
+
+        assert_eq!(map.tiles, load_world(&dir).unwrap().tiles);
+
+        // Saving again without a chunk's tiles leaves none of them behind.
+        let mut edited = map.clone();
+        edited.tiles.retain(|p, _| chunk_of(*p) != (0, 0));
+        save_world(&edited, &dir).unwrap();
+        assert_eq!(edited.tiles, load_world(&dir).unwrap().tiles);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use crate::camera::CameraMode;
+use crate::direction::Corners;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// Explore maps in the terminal and complete quests.
+///
+/// Exit codes: 0 success, 2 missing or invalid arguments, 3 map not found, 4 map in incorrect
+/// format, 5 quest not supported, 6 file could not be written, 7 map failed validation,
+/// 8 no solution found, 9 recording could not be loaded, 10 key bindings could not be loaded.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play a map
+    Play {
+        #[command(flatten)]
+        game: GameArgs,
+        /// Record every move to this file, to be watched with `replay`
+        #[arg(long)]
+        save: Option<PathBuf>,
+        /// A key binding file, see `keys/wasd.ron`. Defaults to the arrow keys
+        #[arg(long)]
+        keys: Option<PathBuf>,
+    },
+    /// Check a map for mistakes without playing it
+    Validate {
+        #[arg(long)]
+        map: PathBuf,
+    },
+    /// Open a map in the editor, or start a new one if it doesn't exist
+    Edit {
+        #[arg(long)]
+        map: PathBuf,
+    },
+    /// Look for moves that complete the quest
+    Solve {
+        #[command(flatten)]
+        game: GameArgs,
+        /// Save the solution to this file, to be watched with `replay`
+        #[arg(long)]
+        save: Option<PathBuf>,
+    },
+    /// Rewrite a map in another format
+    Convert {
+        #[arg(long)]
+        map: PathBuf,
+        /// Where to write the converted map
+        #[arg(long)]
+        save: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Ascii)]
+        format: Format,
+    },
+    /// Watch a game saved by `play --save` or `solve --save`
+    Replay {
+        /// The saved game
+        recording: PathBuf,
+        /// A key binding file, see `keys/wasd.ron`
+        #[arg(long)]
+        keys: Option<PathBuf>,
+    },
+    /// Generate a new map
+    Generate {
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Where to write the map
+        #[arg(long)]
+        save: PathBuf,
+        #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(i32).range(5..))]
+        width: i32,
+        #[arg(long, default_value_t = 40, value_parser = clap::value_parser!(i32).range(5..))]
+        height: i32,
+        /// Write a world directory of chunks that are loaded as the player gets near them,
+        /// instead of a single file. Meant for very large maps
+        #[arg(long)]
+        chunked: bool,
+    },
+}
+
+/// Which map to play, and how.
+#[derive(Args)]
+pub struct GameArgs {
+    /// The map to play (a `.ron` map or a Tiled `.json` export)
+    #[arg(long, required_unless_present = "seed", conflicts_with = "seed")]
+    pub map: Option<PathBuf>,
+    /// Play a map generated from this seed instead
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// The quest to play: q1, q2, q3, q4, q5, q6 or q7. Defaults to the quest the map names
+    #[arg(long)]
+    pub quest: Option<String>,
+    /// Where the player starts, as `x,y`. Defaults to the map's spawn point
+    #[arg(long, value_parser = parse_spawn, allow_hyphen_values = true)]
+    pub spawn: Option<(i32, i32)>,
+    /// How the screen follows the player: edge, centered, page, dead-zone or dead-zone:WxH.
+    /// Defaults to the map's choice, or edge
+    #[arg(long)]
+    pub camera: Option<CameraMode>,
+    /// Turn on fog of war: only tiles this many steps away, and not behind barriers or rocks,
+    /// can be seen. Defaults to the map's rules
+    #[arg(long)]
+    pub sight: Option<i32>,
+    /// Move diagonally too. Diagonal steps past barriers are always allowed with `cut`, need
+    /// one side free with `squeeze`, and both sides free with `clear` (the default)
+    #[arg(long, num_args = 0..=1, default_missing_value = "clear")]
+    pub diagonal: Option<Corners>,
+    /// How many moves can be undone. Defaults to the map's rules, or 100
+    #[arg(long)]
+    pub undo: Option<usize>,
+    /// Turn undo off
+    #[arg(long)]
+    pub hardcore: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// A character grid with a legend
+    Ascii,
+    /// One `(x, y): Block` entry per tile
+    Tiles,
+}
+
+fn parse_spawn(arg: &str) -> Result<(i32, i32), String> {
+    let error = || format!("expected `x,y`, found `{}`", arg);
+    let (x, y) = arg.split_once(',').ok_or_else(error)?;
+    match (x.trim().parse(), y.trim().parse()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(error()),
+    }
+}
@@ -0,0 +1,200 @@
+use crate::direction::Direction;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How a creature gets about.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Behaviour {
+    /// A step in a random direction every turn.
+    #[default]
+    Wander,
+    /// Walks to each of these positions in turn, and back to the first after the last.
+    Patrol(Vec<(i32, i32)>),
+    /// Goes after the player once they come within `radius` steps, and wanders otherwise.
+    Chase { radius: i32 },
+}
+
+/// Something that moves about the map on its own, read from `<map>.creatures.ron` next to
+/// the map. Creatures stand in the way of the player, and fight them if they have an `attack`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Creature {
+    pub name: String,
+    #[serde(default = "default_glyph")]
+    pub glyph: char,
+    /// Where it is.
+    pub at: (i32, i32),
+    #[serde(default)]
+    pub behaviour: Behaviour,
+    /// How hard it hits. Without any, it never attacks.
+    #[serde(default)]
+    pub attack: i32,
+    /// How much of each hit it shrugs off.
+    #[serde(default)]
+    pub defense: i32,
+    /// How much damage it takes to defeat.
+    #[serde(default = "default_health")]
+    pub health: i32,
+    /// The object it drops when defeated.
+    #[serde(default)]
+    pub loot: Option<char>,
+    /// How many of the player's turns pass between two of its moves.
+    #[serde(default = "default_speed")]
+    pub speed: u32,
+    /// The position in its patrol route it is heading for.
+    #[serde(skip)]
+    waypoint: usize,
+    /// Whether it is going after the player.
+    #[serde(skip)]
+    chasing: bool,
+}
+
+/// How much health a hit with `attack` takes off something with `defense`. A hit always does
+/// some damage.
+pub fn damage(attack: i32, defense: i32) -> i32 {
+    (attack - defense).max(1)
+}
+
+fn default_glyph() -> char {
+    '&'
+}
+
+fn default_health() -> i32 {
+    1
+}
+
+fn default_speed() -> u32 {
+    1
+}
+
+/// What a creature did on its turn.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Act {
+    Stay,
+    /// Moved, from here.
+    Moved((i32, i32)),
+    /// Tried to move onto the player, attacking them.
+    Attack,
+    /// Lost track of the player it was chasing.
+    LostTrack,
+}
+
+impl Creature {
+    /// Takes a turn, with the player at `player`. `free` tells which tiles the creature could
+    /// stand on, not counting the player.
+    pub fn act(
+        &mut self,
+        player: (i32, i32),
+        free: impl Fn((i32, i32)) -> bool,
+        rng: &mut impl Rng,
+    ) -> Act {
+        let distance = (self.at.0 - player.0).abs() + (self.at.1 - player.1).abs();
+        let target = match &self.behaviour {
+            Behaviour::Wander => None,
+            Behaviour::Patrol(route) if route.is_empty() => return Act::Stay,
+            Behaviour::Patrol(route) => {
+                if self.at == route[self.waypoint % route.len()] {
+                    self.waypoint = (self.waypoint + 1) % route.len();
+                }
+                Some(route[self.waypoint % route.len()])
+            }
+            Behaviour::Chase { radius } if distance <= *radius => {
+                self.chasing = true;
+                Some(player)
+            }
+            Behaviour::Chase { .. } if self.chasing => {
+                self.chasing = false;
+                return Act::LostTrack;
+            }
+            Behaviour::Chase { .. } => None,
+        };
+        let direction = match target {
+            Some(target) => towards(self.at, target, |p| p == player || free(p)),
+            None => Direction::CARDINAL.choose(rng).copied(),
+        };
+        let to = match direction {
+            Some(direction) => direction.step(self.at),
+            None => return Act::Stay,
+        };
+        if to == player {
+            return match self.attack > 0 {
+                true => Act::Attack,
+                false => Act::Stay,
+            };
+        }
+        if !free(to) {
+            return Act::Stay;
+        }
+        let from = self.at;
+        self.at = to;
+        Act::Moved(from)
+    }
+}
+
+/// The step from `from` that gets closest to `to`, if any gets closer at all.
+fn towards(
+    from: (i32, i32),
+    to: (i32, i32),
+    open: impl Fn((i32, i32)) -> bool,
+) -> Option<Direction> {
+    let distance = |(x, y): (i32, i32)| (x - to.0).abs() + (y - to.1).abs();
+    Direction::CARDINAL
+        .iter()
+        .map(|direction| (*direction, direction.step(from)))
+        .filter(|(_, next)| distance(*next) < distance(from) && open(*next))
+        .min_by_key(|(_, next)| distance(*next))
+        .map(|(direction, _)| direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creatures::{damage, Act, Behaviour, Creature};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn creature(behaviour: Behaviour) -> Creature {
+        let mut creature: Creature =
+            ron::from_str("(name: \"Wolf\", at: (5, 5), attack: 2)").unwrap();
+        creature.behaviour = behaviour;
+        creature
+    }
+
+    #[test]
+    fn chases_patrols_and_wanders() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let open = |p: (i32, i32)| p != (5, 4);
+
+        let mut wolf = creature(Behaviour::Chase { radius: 3 });
+        assert_eq!(Act::Moved((5, 5)), wolf.act((7, 5), open, &mut rng));
+        assert_eq!(Act::Attack, wolf.act((7, 5), open, &mut rng));
+        assert_eq!(Act::LostTrack, wolf.act((20, 5), open, &mut rng));
+
+        let mut guard = creature(Behaviour::Patrol(vec![(5, 5), (5, 3)]));
+        // The way up is blocked, so the guard waits.
+        assert_eq!(Act::Stay, guard.act((0, 0), open, &mut rng));
+        assert_eq!((5, 5), guard.at);
+        let mut guard = creature(Behaviour::Patrol(vec![(7, 5), (5, 5)]));
+        guard.act((0, 0), open, &mut rng);
+        guard.act((0, 0), open, &mut rng);
+        assert_eq!((7, 5), guard.at);
+        guard.act((0, 0), open, &mut rng);
+        assert_eq!((6, 5), guard.at);
+
+        let mut rabbit = creature(Behaviour::Wander);
+        for _ in 0..20 {
+            rabbit.act((0, 0), |p| p.0 >= 5 && p.1 >= 5, &mut rng);
+        }
+        assert!(rabbit.at.0 >= 5 && rabbit.at.1 >= 5);
+
+        let village: Vec<Creature> =
+            ron::from_str(include_str!("../../maps/village.creatures.ron")).unwrap();
+        assert_eq!(Behaviour::Chase { radius: 5 }, village[2].behaviour);
+        assert_eq!(Some('f'), village[2].loot);
+    }
+
+    #[test]
+    fn hits_always_hurt() {
+        assert_eq!(3, damage(4, 1));
+        assert_eq!(1, damage(1, 5));
+    }
+}
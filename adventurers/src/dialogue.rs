@@ -0,0 +1,126 @@
+use crate::keys::{Action, KeyBindings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use termgame::Message;
+
+/// Everyone a map's `Npc("name")` tiles can be, read from `<map>.npcs.ron` next to the map.
+pub type Npcs = HashMap<String, Npc>;
+
+/// What an NPC says, as lines that lead to one another through the player's choices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Npc {
+    /// The line said first.
+    pub start: String,
+    pub lines: HashMap<String, Line>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Line {
+    pub text: String,
+    /// What the player can answer. Without any, the conversation ends here.
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Choice {
+    pub text: String,
+    /// The line the NPC answers with, or none to end the conversation.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Tells the quest `Event::Chose(event)` when picked.
+    #[serde(default)]
+    pub event: Option<String>,
+}
+
+/// A conversation going on with an NPC.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub npc: String,
+    line: String,
+}
+
+impl Conversation {
+    /// Starts talking to `npc`, if it has anything to say.
+    pub fn start(npcs: &Npcs, npc: &str) -> Option<Self> {
+        let start = &npcs.get(npc)?.start;
+        Some(Conversation {
+            npc: npc.to_string(),
+            line: start.clone(),
+        })
+    }
+
+    /// The line the NPC is saying.
+    pub fn line<'a>(&self, npcs: &'a Npcs) -> Option<&'a Line> {
+        npcs.get(&self.npc)?.lines.get(&self.line)
+    }
+
+    /// The line the NPC is saying, with the choices numbered, or the key from `keys` that
+    /// leaves if there are none.
+    pub fn view(&self, npcs: &Npcs, keys: &KeyBindings) -> Message {
+        let text = match self.line(npcs) {
+            Some(line) if line.choices.is_empty() => {
+                format!("{}\n({} to leave)", line.text, keys.describe(Action::Close))
+            }
+            Some(line) => {
+                let choices = line
+                    .choices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, choice)| format!("{}. {}", i + 1, choice.text))
+                    .collect::<Vec<_>>();
+                format!("{}\n{}", line.text, choices.join("\n"))
+            }
+            None => format!("{} has lost their train of thought.", self.npc),
+        };
+        Message::new(text).title(self.npc.clone())
+    }
+
+    /// Answers with choice `index`, counting from 0, and moves on to the NPC's answer. Returns
+    /// the choice if there is one, and whether the conversation goes on.
+    pub fn choose(&mut self, npcs: &Npcs, index: usize) -> Option<(Choice, bool)> {
+        let choice = self.line(npcs)?.choices.get(index)?.clone();
+        match &choice.next {
+            Some(next) => {
+                self.line = next.clone();
+                Some((choice, true))
+            }
+            None => Some((choice, false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dialogue::{Conversation, Npcs};
+    use crate::keys::KeyBindings;
+
+    #[test]
+    fn choices_lead_to_lines() {
+        let npcs: Npcs = ron::from_str(include_str!("../../maps/village.npcs.ron")).unwrap();
+        let mut talk = Conversation::start(&npcs, "Hermit").unwrap();
+        let keys = KeyBindings::default();
+        assert!(talk.view(&npcs, &keys).text.contains("1. Who are you?"));
+        let (choice, goes_on) = talk.choose(&npcs, 0).unwrap();
+        assert_eq!(None, choice.event);
+        assert!(goes_on);
+        assert!(talk.choose(&npcs, 7).is_none());
+
+        let (choice, goes_on) = talk.choose(&npcs, 0).unwrap();
+        assert_eq!(Some("help".to_string()), choice.event);
+        assert!(goes_on);
+        let (_, goes_on) = talk.choose(&npcs, 0).unwrap();
+        assert!(!goes_on);
+        assert!(Conversation::start(&npcs, "Nobody").is_none());
+    }
+
+    #[test]
+    fn leaving_names_the_bound_key() {
+        let npcs: Npcs =
+            ron::from_str("{ \"Guard\": (start: \"hi\", lines: { \"hi\": (text: \"Halt\") }) }")
+                .unwrap();
+        let talk = Conversation::start(&npcs, "Guard").unwrap();
+        let keys = KeyBindings::parse("{ Close: [\"q\"] }").unwrap();
+        assert_eq!("Halt\n(q to leave)", talk.view(&npcs, &keys).text);
+    }
+}
@@ -1,6 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// The four directions along the axes.
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// All eight directions, cardinal ones first.
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// How far one step moves, across and down.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    /// Where one step from `(x, y)` ends up.
+    pub fn step(self, (x, y): (i32, i32)) -> (i32, i32) {
+        let (dx, dy) = self.offset();
+        (x + dx, y + dy)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let (dx, dy) = self.offset();
+        dx != 0 && dy != 0
+    }
+
+    /// The two tiles a diagonal step from `from` passes between, or none for other steps.
+    pub fn corners(self, (x, y): (i32, i32)) -> Option<[(i32, i32); 2]> {
+        let (dx, dy) = self.offset();
+        self.is_diagonal().then_some([(x + dx, y), (x, y + dy)])
+    }
+
+    /// A short name for printing a list of moves.
+    pub fn symbol(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+            Direction::UpLeft => '↖',
+            Direction::UpRight => '↗',
+            Direction::DownLeft => '↙',
+            Direction::DownRight => '↘',
+        }
+    }
+}
+
+/// With diagonal movement on, whether a diagonal step may squeeze between the tiles on its
+/// corners when they can't be walked on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Corners {
+    /// Diagonal steps are allowed whatever is on their corners.
+    Cut,
+    /// Allowed as long as one of the two corners can be walked on.
+    Squeeze,
+    /// Only allowed when both corners can be walked on.
+    #[default]
+    Clear,
+}
+
+impl Corners {
+    /// Whether a diagonal step is allowed, given whether each of its corners can be walked on.
+    pub fn allow(self, [first, second]: [bool; 2]) -> bool {
+        match self {
+            Corners::Cut => true,
+            Corners::Squeeze => first || second,
+            Corners::Clear => first && second,
+        }
+    }
+}
+
+impl FromStr for Corners {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Self, Self::Err> {
+        match arg {
+            "cut" => Ok(Corners::Cut),
+            "squeeze" => Ok(Corners::Squeeze),
+            "clear" => Ok(Corners::Clear),
+            _ => Err(format!("expected cut, squeeze or clear, found `{}`", arg)),
+        }
+    }
+}
+
+/// The directions a player can move in: all eight with `diagonal` on, otherwise the cardinal
+/// ones.
+pub fn directions(diagonal: Option<Corners>) -> &'static [Direction] {
+    match diagonal {
+        Some(_) => &Direction::ALL,
+        None => &Direction::CARDINAL,
+    }
+}
+
+/// Whether a step from `from` in `direction` is allowed, with `walkable` telling which tiles
+/// can be walked on. It doesn't check the tile stepped onto.
+pub fn can_step(
+    diagonal: Option<Corners>,
+    from: (i32, i32),
+    direction: Direction,
+    walkable: impl Fn((i32, i32)) -> bool,
+) -> bool {
+    match (direction.corners(from), diagonal) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some([a, b]), Some(corners)) => corners.allow([walkable(a), walkable(b)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::direction::{can_step, Corners, Direction};
+
+    #[test]
+    fn corner_rules() {
+        // A barrier at (1, 1), just right of (0, 1).
+        let walkable = |p: (i32, i32)| p != (1, 1);
+        let from = (0, 1);
+        assert!(can_step(None, from, Direction::Up, walkable));
+        assert!(!can_step(None, from, Direction::UpRight, walkable));
+        assert!(can_step(
+            Some(Corners::Cut),
+            from,
+            Direction::UpRight,
+            walkable
+        ));
+        assert!(can_step(
+            Some(Corners::Squeeze),
+            from,
+            Direction::UpRight,
+            walkable
+        ));
+        assert!(!can_step(
+            Some(Corners::Clear),
+            from,
+            Direction::UpRight,
+            walkable
+        ));
+        assert!(can_step(
+            Some(Corners::Clear),
+            from,
+            Direction::DownLeft,
+            walkable
+        ));
+        assert_eq!((1, 0), Direction::UpRight.step(from));
+    }
 }
@@ -0,0 +1,247 @@
+use crate::ascii::AsciiMap;
+use crate::block::Block;
+use crate::camera::Camera;
+use crate::chunks;
+use crate::map::MapFile;
+use crate::palette::Palette;
+use std::path::PathBuf;
+use termgame::{Controller, Game, GameEvent, KeyCode, Message, SimpleEvent, StyledCharacter};
+
+const HELP: &str = "Arrows move the cursor. Paint with 1 Grass, 2 Sand, 3 Rock, 4 Cinderblock,
+5 Flowerbush, 6 Barrier, 7 Water, 8 Sign, 9 Object, k Checkpoint, 0 Erase.
+c paints the custom tile chosen with Tab, p puts the spawn point here.
+s saves, Esc quits.";
+
+/// What the editor is waiting for the user to type.
+enum Prompt {
+    SignText(String),
+    ObjectChar,
+    ConfirmQuit,
+}
+
+/// A [`Controller`] for painting maps, started with `adventurers edit path/to/map.ron`.
+pub struct Editor {
+    path: PathBuf,
+    ascii: bool,
+    map: MapFile,
+    palette: Palette,
+    x: i32,
+    y: i32,
+    custom: usize,
+    prompt: Option<Prompt>,
+    saved: bool,
+}
+
+impl Editor {
+    /// Edits `map`, which will be saved to `path`.
+    ///
+    /// Maps are saved in the tile format, unless `ascii` is set, and worlds back into their
+    /// directory. Tiled exports are never overwritten, their edits are saved next to them with a `.ron` extension.
+    pub fn new(path: PathBuf, map: MapFile, ascii: bool) -> Self {
+        let path = match path.extension() {
+            Some(ext) if ext == "json" => path.with_extension("ron"),
+            _ => path,
+        };
+        let (x, y) = map.meta.spawn();
+        Editor {
+            path,
+            ascii,
+            palette: Palette::new(map.palette.clone()),
+            map,
+            x,
+            y,
+            custom: 0,
+            prompt: None,
+            saved: true,
+        }
+    }
+
+    fn draw_tile(&self, game: &mut Game, x: i32, y: i32) {
+        let styled = self.map.tiles.get(&(x, y)).map(|b| self.palette.style(b));
+        game.set_screen_char(x, y, styled);
+    }
+
+    fn show_cursor(&self, game: &mut Game) {
+        match game.get_screen_char(self.x, self.y) {
+            Some(prev) => game.set_screen_char(self.x, self.y, Some(prev.character('+'))),
+            None => game.set_screen_char(self.x, self.y, Some(StyledCharacter::from('+'))),
+        }
+        Camera::new(self.map.meta.camera.unwrap_or_default()).follow(game, (self.x, self.y));
+    }
+
+    fn move_cursor(&mut self, game: &mut Game, dx: i32, dy: i32) {
+        self.draw_tile(game, self.x, self.y);
+        self.x += dx;
+        self.y += dy;
+        self.show_cursor(game);
+        self.status(game);
+    }
+
+    fn paint(&mut self, game: &mut Game, block: Option<Block>) {
+        match block {
+            Some(block) => self.map.tiles.insert((self.x, self.y), block),
+            None => self.map.tiles.remove(&(self.x, self.y)),
+        };
+        self.saved = false;
+        self.draw_tile(game, self.x, self.y);
+        self.show_cursor(game);
+        self.status(game);
+    }
+
+    fn status(&self, game: &mut Game) {
+        let tile = match self.map.tiles.get(&(self.x, self.y)) {
+            Some(block) => format!("{:?}", block),
+            None => String::from("nothing"),
+        };
+        let spawn = match self.map.meta.spawn {
+            Some(spawn) if spawn == (self.x, self.y) => " (spawn)",
+            _ => "",
+        };
+        let changed = if self.saved { "" } else { " [modified]" };
+        game.set_message(Some(Message::new(format!(
+            "({}, {}): {}{}{}",
+            self.x, self.y, tile, spawn, changed
+        ))));
+    }
+
+    fn save(&mut self, game: &mut Game) {
+        let saved = if self.path.is_dir() {
+            chunks::save_world(&self.map, &self.path).map_err(|err| err.to_string())
+        } else if self.ascii {
+            AsciiMap::from_map_file(self.map.clone())
+                .map_err(|err| err.to_string())
+                .and_then(|ascii| ascii.to_ron().map_err(|err| err.to_string()))
+                .and_then(|text| std::fs::write(&self.path, text).map_err(|err| err.to_string()))
+        } else {
+            self.map
+                .to_ron()
+                .map_err(|err| err.to_string())
+                .and_then(|text| std::fs::write(&self.path, text).map_err(|err| err.to_string()))
+        };
+        match saved {
+            Err(err) => game.set_message(Some(Message::new(format!(
+                "Could not save to {}: {}",
+                self.path.display(),
+                err
+            )))),
+            Ok(()) => {
+                self.saved = true;
+                game.set_message(Some(Message::new(format!(
+                    "Saved to {}",
+                    self.path.display()
+                ))));
+            }
+        }
+    }
+
+    fn custom_name(&self) -> Option<String> {
+        self.map.palette.get(self.custom).map(|t| t.name.clone())
+    }
+
+    fn on_prompt(&mut self, game: &mut Game, prompt: Prompt, code: KeyCode) {
+        match (prompt, code) {
+            (_, KeyCode::Esc) => self.status(game),
+            (Prompt::SignText(text), KeyCode::Enter) => self.paint(game, Some(Block::Sign(text))),
+            (Prompt::SignText(mut text), KeyCode::Backspace) => {
+                text.pop();
+                self.ask(game, Prompt::SignText(text));
+            }
+            (Prompt::SignText(mut text), KeyCode::Char(c)) => {
+                text.push(c);
+                self.ask(game, Prompt::SignText(text));
+            }
+            (Prompt::ObjectChar, KeyCode::Char(c)) => self.paint(game, Some(Block::Object(c))),
+            (Prompt::ConfirmQuit, KeyCode::Char('y')) => game.end_game(),
+            (Prompt::ConfirmQuit, _) => self.status(game),
+            (prompt, _) => self.ask(game, prompt),
+        }
+    }
+
+    fn ask(&mut self, game: &mut Game, prompt: Prompt) {
+        let question = match &prompt {
+            Prompt::SignText(text) => {
+                format!("Sign text (Enter to place, Esc to cancel): {}", text)
+            }
+            Prompt::ObjectChar => String::from("Type the object's character (Esc to cancel)"),
+            Prompt::ConfirmQuit => String::from("There are unsaved changes, quit anyway? (y/n)"),
+        };
+        game.set_message(Some(Message::new(question)));
+        self.prompt = Some(prompt);
+    }
+}
+
+fn brush(c: char) -> Option<Block> {
+    match c {
+        '1' => Some(Block::Grass),
+        '2' => Some(Block::Sand),
+        '3' => Some(Block::Rock),
+        '4' => Some(Block::Cinderblock),
+        '5' => Some(Block::Flowerbush),
+        '6' => Some(Block::Barrier),
+        '7' => Some(Block::Water),
+        'k' => Some(Block::Checkpoint),
+        _ => None,
+    }
+}
+
+impl Controller for Editor {
+    fn on_start(&mut self, game: &mut Game) {
+        for key in self.map.tiles.keys() {
+            self.draw_tile(game, key.0, key.1);
+        }
+        self.show_cursor(game);
+        game.set_message(Some(
+            Message::new(String::from(HELP)).title(format!("Editing {}", self.path.display())),
+        ));
+    }
+
+    fn on_event(&mut self, game: &mut Game, event: GameEvent) {
+        let code = match event.into() {
+            SimpleEvent::Just(code) => code,
+            _ => return,
+        };
+        if let Some(prompt) = self.prompt.take() {
+            self.on_prompt(game, prompt, code);
+            return;
+        }
+        match code {
+            KeyCode::Up => self.move_cursor(game, 0, -1),
+            KeyCode::Down => self.move_cursor(game, 0, 1),
+            KeyCode::Left => self.move_cursor(game, -1, 0),
+            KeyCode::Right => self.move_cursor(game, 1, 0),
+            KeyCode::Char('0') => self.paint(game, None),
+            KeyCode::Char('8') => self.ask(game, Prompt::SignText(String::new())),
+            KeyCode::Char('9') => self.ask(game, Prompt::ObjectChar),
+            KeyCode::Char('c') => match self.custom_name() {
+                Some(name) => self.paint(game, Some(Block::Custom(name))),
+                None => game.set_message(Some(Message::new(String::from(
+                    "This map has no custom tiles in its palette",
+                )))),
+            },
+            KeyCode::Tab => {
+                if !self.map.palette.is_empty() {
+                    self.custom = (self.custom + 1) % self.map.palette.len();
+                }
+                if let Some(name) = self.custom_name() {
+                    game.set_message(Some(Message::new(format!("c now paints {}", name))));
+                }
+            }
+            KeyCode::Char('p') => {
+                self.map.meta.spawn = Some((self.x, self.y));
+                self.saved = false;
+                self.status(game);
+            }
+            KeyCode::Char('s') => self.save(game),
+            KeyCode::Esc if self.saved => game.end_game(),
+            KeyCode::Esc => self.ask(game, Prompt::ConfirmQuit),
+            KeyCode::Char(c) => {
+                if let Some(block) = brush(c) {
+                    self.paint(game, Some(block));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_tick(&mut self, _game: &mut Game) {}
+}
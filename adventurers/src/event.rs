@@ -0,0 +1,21 @@
+use crate::block::Block;
+
+/// Something that happens to the player, which quests are told about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The player stepped on this tile, or on `Empty` off the edge of the map.
+    Tile(Block),
+    /// The player has seen this many percent of the map with fog of war on, so quests can ask
+    /// for part of the map to be explored.
+    Explored(u32),
+    /// The player talked to the NPC with this name.
+    Talked(String),
+    /// The player picked a dialogue choice with this event.
+    Chose(String),
+    /// The player defeated the creature with this name.
+    Defeated(String),
+    /// The creature with this name lost track of the player.
+    Avoided(String),
+    /// The player used the item with this name.
+    Used(String),
+}
@@ -0,0 +1,168 @@
+use crate::block::Block;
+use crate::chunks::ChunkedMap;
+use crate::palette::Palette;
+use std::collections::HashSet;
+use termgame::StyledCharacter;
+
+/// What the player knows about the map, with fog of war on.
+///
+/// Tiles within `radius` of the player are in sight unless a barrier or rock stands in the
+/// way. Tiles the player has seen before stay on screen, dimmed, and the rest are hidden.
+pub struct Fog {
+    radius: i32,
+    /// Every position the player has seen.
+    seen: HashSet<(i32, i32)>,
+    /// The positions in sight right now.
+    visible: HashSet<(i32, i32)>,
+    /// How many of the positions seen have a tile.
+    explored: usize,
+}
+
+impl Fog {
+    pub fn new(radius: i32) -> Self {
+        Fog {
+            radius,
+            seen: HashSet::new(),
+            visible: HashSet::new(),
+            explored: 0,
+        }
+    }
+
+    /// Looks around from `from`, and returns the positions that came into or went out of
+    /// sight, which need to be drawn again.
+    pub fn look(&mut self, map: &ChunkedMap, from: (i32, i32)) -> Vec<(i32, i32)> {
+        let (x, y) = from;
+        let r = self.radius;
+        let visible = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (x + dx, y + dy)))
+            .filter(|&(tx, ty)| (tx - x).pow(2) + (ty - y).pow(2) <= r * r)
+            .filter(|&to| in_sight(map, from, to))
+            .collect::<HashSet<_>>();
+        for position in &visible {
+            if self.seen.insert(*position) && map.get(position).is_some() {
+                self.explored += 1;
+            }
+        }
+        let changed = self
+            .visible
+            .symmetric_difference(&visible)
+            .copied()
+            .collect();
+        self.visible = visible;
+        changed
+    }
+
+    /// Whether the player has ever seen `position`.
+    pub fn seen(&self, position: (i32, i32)) -> bool {
+        self.seen.contains(&position)
+    }
+
+    /// Whether `position` is in sight right now.
+    pub fn in_view(&self, position: (i32, i32)) -> bool {
+        self.visible.contains(&position)
+    }
+
+    /// How much of the map has been seen, in percent.
+    pub fn percent_explored(&self, map: &ChunkedMap) -> u32 {
+        match map.size() {
+            0 => 100,
+            size => (self.explored * 100 / size).min(100) as u32,
+        }
+    }
+
+    /// How the tile at `position` looks: as usual in sight, dimmed if it has been seen
+    /// before, and not at all otherwise.
+    pub fn style(
+        &self,
+        map: &ChunkedMap,
+        palette: &Palette,
+        position: (i32, i32),
+    ) -> Option<StyledCharacter> {
+        let block = map.get(&position)?;
+        if self.visible.contains(&position) {
+            Some(palette.style(block))
+        } else if self.seen.contains(&position) {
+            Some(palette.dimmed(block))
+        } else {
+            None
+        }
+    }
+}
+
+fn blocks_sight(block: &Block) -> bool {
+    matches!(block, Block::Barrier | Block::Rock)
+}
+
+/// Whether nothing between `from` and `to` blocks sight. The tiles at either end don't
+/// count, so a wall can be seen, just not what is behind it.
+fn in_sight(map: &ChunkedMap, from: (i32, i32), to: (i32, i32)) -> bool {
+    let line = line(from, to);
+    line.iter()
+        .skip(1)
+        .take(line.len().saturating_sub(2))
+        .all(|position| !map.get(position).is_some_and(blocks_sight))
+}
+
+/// The positions on a straight line from `from` to `to`, both included, by Bresenham's
+/// algorithm.
+pub fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut line = vec![(x, y)];
+    while (x, y) != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        line.push((x, y));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::chunks::ChunkedMap;
+    use crate::fog::{line, Fog};
+    use crate::palette::Palette;
+    use std::collections::HashMap;
+
+    #[test]
+    fn lines() {
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0)], line((0, 0), (2, 0)));
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2)], line((0, 0), (3, 2)));
+        assert_eq!(vec![(2, 2)], line((2, 2), (2, 2)));
+        assert_eq!(vec![(0, 0), (-1, -1)], line((0, 0), (-1, -1)));
+    }
+
+    #[test]
+    fn walls_block_sight() {
+        let mut tiles = (0..10)
+            .map(|x| ((x, 0), Block::Grass))
+            .collect::<HashMap<_, _>>();
+        tiles.insert((3, 0), Block::Rock);
+        let map = ChunkedMap::new(tiles);
+        let palette = Palette::new(vec![]);
+        let mut fog = Fog::new(5);
+
+        fog.look(&map, (0, 0));
+        assert!(fog.style(&map, &palette, (3, 0)).is_some());
+        assert!(fog.style(&map, &palette, (4, 0)).is_none());
+        assert_eq!(40, fog.percent_explored(&map));
+
+        let changed = fog.look(&map, (9, 0));
+        assert!(changed.contains(&(0, 0)));
+        assert!(changed.contains(&(4, 0)));
+        assert!(fog.style(&map, &palette, (0, 0)).is_some());
+        assert_eq!(100, fog.percent_explored(&map));
+    }
+}
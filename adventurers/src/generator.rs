@@ -0,0 +1,210 @@
+use crate::block::Block;
+use crate::map::{MapFile, DEFAULT_SPAWN as SPAWN};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+const SIGNS: [&str; 5] = [
+    "Hello, wanderer!",
+    "Beware of the deep water.",
+    "Something shiny lies nearby...",
+    "The rocks here are older than the sea.",
+    "Turn back while you still can!",
+];
+
+/// Settings for [`generate`]. The same options always give the same map.
+pub struct GeneratorOptions {
+    pub seed: u64,
+    pub width: i32,
+    pub height: i32,
+    /// How many of each object to scatter, e.g. five `x` and three `y` for quest 2.
+    pub objects: Vec<(char, u32)>,
+    pub signs: u32,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            width: 80,
+            height: 40,
+            objects: vec![('x', 5), ('y', 3)],
+            signs: 3,
+        }
+    }
+}
+
+/// Smoothed random values on a grid, with `octaves` layers of finer and fainter detail.
+struct ValueNoise {
+    layers: Vec<Lattice>,
+}
+
+/// Random values every `spacing` tiles.
+struct Lattice {
+    spacing: i32,
+    values: HashMap<(i32, i32), f64>,
+}
+
+impl ValueNoise {
+    fn new(rng: &mut ChaCha8Rng, width: i32, height: i32, octaves: u32) -> Self {
+        let layers = (0..octaves)
+            .map(|octave| {
+                let spacing = (16 >> octave).max(1);
+                let values = (0..=width / spacing + 1)
+                    .flat_map(|x| (0..=height / spacing + 1).map(move |y| (x, y)))
+                    .map(|point| (point, rng.gen::<f64>()))
+                    .collect();
+                Lattice { spacing, values }
+            })
+            .collect();
+        ValueNoise { layers }
+    }
+
+    /// A value between 0 and 1.
+    fn get(&self, x: i32, y: i32) -> f64 {
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        let mut weights = 0.0;
+        for Lattice { spacing, values } in &self.layers {
+            let (cx, cy) = (x / spacing, y / spacing);
+            let fx = smooth((x % spacing) as f64 / *spacing as f64);
+            let fy = smooth((y % spacing) as f64 / *spacing as f64);
+            let top = lerp(values[&(cx, cy)], values[&(cx + 1, cy)], fx);
+            let bottom = lerp(values[&(cx, cy + 1)], values[&(cx + 1, cy + 1)], fx);
+            total += lerp(top, bottom, fy) * weight;
+            weights += weight;
+            weight /= 2.0;
+        }
+        total / weights
+    }
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+fn terrain(height: f64) -> Block {
+    match height {
+        h if h < 0.32 => Block::Water,
+        h if h < 0.4 => Block::Sand,
+        h if h < 0.68 => Block::Grass,
+        h if h < 0.72 => Block::Flowerbush,
+        _ => Block::Rock,
+    }
+}
+
+/// Generates a map surrounded by barriers, with lakes, beaches, meadows and rocky hills.
+///
+/// The spawn point is always in a clearing of grass, and every object and sign is placed on
+/// land that can be reached from it without swimming.
+pub fn generate(options: &GeneratorOptions) -> MapFile {
+    // ChaCha gives the same numbers on every platform and version of rand, unlike `StdRng`,
+    // so a seed keeps making the same map.
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    let noise = ValueNoise::new(&mut rng, options.width, options.height, 3);
+    let mut map = MapFile::default();
+
+    for x in 0..options.width {
+        for y in 0..options.height {
+            let border = x == 0 || y == 0 || x == options.width - 1 || y == options.height - 1;
+            let block = if border {
+                Block::Barrier
+            } else if (x - SPAWN.0).abs() <= 1 && (y - SPAWN.1).abs() <= 1 {
+                Block::Grass
+            } else {
+                terrain(noise.get(x, y))
+            };
+            map.tiles.insert((x, y), block);
+        }
+    }
+
+    connect_spawn(&mut map);
+
+    let mut land: Vec<(i32, i32)> = map
+        .reachable(SPAWN, false)
+        .into_iter()
+        .filter(|pos| *pos != SPAWN)
+        .collect();
+    // Sorted first, so the shuffle only depends on the seed.
+    land.sort();
+    land.shuffle(&mut rng);
+    let mut spots = land.into_iter();
+
+    for (c, count) in &options.objects {
+        for pos in spots.by_ref().take(*count as usize) {
+            map.tiles.insert(pos, Block::Object(*c));
+        }
+    }
+    for pos in spots.take(options.signs as usize) {
+        let text = SIGNS[rng.gen_range(0..SIGNS.len())];
+        map.tiles.insert(pos, Block::Sign(text.to_string()));
+    }
+    map
+}
+
+/// If a lake cuts the spawn off from the largest stretch of land, lays a sand
+/// causeway from the spawn to its nearest shore.
+fn connect_spawn(map: &mut MapFile) {
+    let spawn_region = map.reachable(SPAWN, false);
+    let largest = match map.regions(false).into_iter().max_by_key(|r| r.len()) {
+        Some(largest) if largest.len() > spawn_region.len() => largest,
+        _ => return,
+    };
+
+    let distance = |pos: &&(i32, i32)| (pos.0 - SPAWN.0).abs() + (pos.1 - SPAWN.1).abs();
+    let target = *largest
+        .iter()
+        .min_by_key(|pos| (distance(pos), **pos))
+        .unwrap();
+    let path = (SPAWN.0.min(target.0)..=SPAWN.0.max(target.0))
+        .map(|x| (x, SPAWN.1))
+        .chain((SPAWN.1.min(target.1)..=SPAWN.1.max(target.1)).map(|y| (target.0, y)));
+    for pos in path {
+        if let Some(block @ Block::Water) = map.tiles.get_mut(&pos) {
+            *block = Block::Sand;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::generator::{generate, GeneratorOptions};
+    use crate::map::DEFAULT_SPAWN as SPAWN;
+
+    #[test]
+    fn same_seed_same_map() {
+        let options = GeneratorOptions {
+            seed: 42,
+            ..GeneratorOptions::default()
+        };
+        assert_eq!(generate(&options).tiles, generate(&options).tiles);
+    }
+
+    #[test]
+    fn objects_are_reachable() {
+        for seed in 0..10 {
+            let map = generate(&GeneratorOptions {
+                seed,
+                ..GeneratorOptions::default()
+            });
+            assert_eq!(Some(&Block::Grass), map.tiles.get(&SPAWN));
+            assert_eq!(Some(&Block::Barrier), map.tiles.get(&(0, 0)));
+            let reachable = map.reachable(SPAWN, false);
+            let objects: Vec<_> = map
+                .tiles
+                .iter()
+                .filter(|(_, b)| matches!(b, Block::Object(_) | Block::Sign(_)))
+                .collect();
+            assert!(!objects.is_empty());
+            for (pos, _) in objects {
+                assert!(reachable.contains(pos));
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::map::Bounds;
+use crate::player::Player;
+use crate::viewport::visible;
+use termgame::{Game, GameColor, GameStyle, StyledCharacter};
+
+/// The line of status shown under the map: breath, position, quest progress and what the
+/// player is carrying.
+pub fn status_line(player: &Player) -> String {
+    let inventory = match player.inventory.is_empty() {
+        true => String::from("-"),
+        false => player
+            .inventory
+            .iter()
+            .map(|(c, count)| format!("{}×{}", c, count))
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    let lives = match player.lives {
+        Some(lives) => format!(" │ Lives {}", lives),
+        None => String::new(),
+    };
+    format!(
+        "Breath {}/{} │ Health {}/{}{} │ ({}, {}) │ {} │ Bag: {}",
+        player.breath,
+        player.max_breath,
+        player.health,
+        player.max_health,
+        lives,
+        player.x,
+        player.y,
+        player.quest.summary(),
+        inventory
+    )
+}
+
+/// Draws a line of text in the row under the map, which stays free of the player.
+#[derive(Default)]
+pub struct Hud {
+    /// Where on screen it was last drawn.
+    drawn: Option<Bounds>,
+}
+
+impl Hud {
+    /// Draws `text` across the bottom of the screen, cut short if it doesn't fit. Returns where
+    /// it was drawn before, which has to be drawn over again if it moved.
+    pub fn draw(&mut self, game: &mut Game, text: &str) -> Option<Bounds> {
+        let screen = visible(game);
+        let row = Bounds {
+            min: (screen.min.0, screen.max.1 + 1),
+            max: (screen.max.0, screen.max.1 + 1),
+        };
+        let text = text.chars().chain(std::iter::repeat(' '));
+        for ((x, y), c) in row.positions().zip(text) {
+            let style = GameStyle::new()
+                .color(Some(GameColor::Black))
+                .background_color(Some(GameColor::Gray));
+            game.set_screen_char(x, y, Some(StyledCharacter::new(c).style(style)));
+        }
+        let before = self.drawn.replace(row);
+        before.filter(|before| *before != row)
+    }
+
+    /// Where on screen it was last drawn.
+    pub fn drawn(&self) -> Option<Bounds> {
+        self.drawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::direction::Direction;
+    use crate::event::Event;
+    use crate::hud::status_line;
+    use crate::map::MapFile;
+    use crate::player::Player;
+    use crate::replay::simulate;
+    use adventurers_quest::SubQuest;
+
+    #[test]
+    fn shows_breath_position_quest_and_bag() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Object('x'), (4, 2): Water }").unwrap();
+        let quest = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            2,
+            "Collect a 'x'".to_string(),
+        );
+        let mut player = Player::new(map.into(), Box::new(quest));
+        assert_eq!(
+            "Breath 10/10 │ Health 10/10 │ (2, 2) │ Collect a 'x' (0/2) │ Bag: -",
+            status_line(&player)
+        );
+        simulate(&mut player, &[Direction::Right, Direction::Right]);
+        assert_eq!(
+            "Breath 9/10 │ Health 10/10 │ (4, 2) │ Collect a 'x' (1/2) │ Bag: x×1",
+            status_line(&player)
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// What the objects on a map do when used, read from `<map>.items.ron` next to the map.
+pub type Items = Vec<Item>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    /// The object it is, as in `Object(glyph)`.
+    pub glyph: char,
+    pub effect: Effect,
+}
+
+/// What happens when an item is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    /// Gives back this much breath.
+    Breath(i32),
+    /// Gives back this much health.
+    Heal(i32),
+    /// Gets the player in or out of it. Water doesn't take breath while in it.
+    Boat,
+    /// Breaks the rocks next to the player, leaving sand.
+    Mine,
+}
+
+impl Effect {
+    /// Whether the item is gone once used.
+    pub fn used_up(self) -> bool {
+        matches!(self, Effect::Breath(_) | Effect::Heal(_))
+    }
+}
+
+/// The item `glyph` is, if it can be used.
+pub fn find(items: &Items, glyph: char) -> Option<&Item> {
+    items.iter().find(|item| item.glyph == glyph)
+}
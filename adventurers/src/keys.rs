@@ -0,0 +1,323 @@
+use crate::direction::Direction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use termgame::{GameEvent, KeyCode, KeyEvent, KeyModifiers};
+
+/// Something the player can do with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    MoveUpLeft,
+    MoveUpRight,
+    MoveDownLeft,
+    MoveDownRight,
+    /// Keep moving until something gets in the way, see `MyGame::run`.
+    RunUp,
+    RunDown,
+    RunLeft,
+    RunRight,
+    RunUpLeft,
+    RunUpRight,
+    RunDownLeft,
+    RunDownRight,
+    /// Pick a place with a cursor, and walk there.
+    Travel,
+    ShowQuest,
+    ResetQuest,
+    Undo,
+    Redo,
+    /// Write the recording so far to the `--save` file.
+    Save,
+    Inventory,
+    /// Pick an item from the bag, and use it.
+    Use,
+    Minimap,
+    Log,
+    /// Close whatever is open, like the message log.
+    Close,
+}
+
+impl Action {
+    /// Which way a move action goes.
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::MoveUp => Some(Direction::Up),
+            Action::MoveDown => Some(Direction::Down),
+            Action::MoveLeft => Some(Direction::Left),
+            Action::MoveRight => Some(Direction::Right),
+            Action::MoveUpLeft => Some(Direction::UpLeft),
+            Action::MoveUpRight => Some(Direction::UpRight),
+            Action::MoveDownLeft => Some(Direction::DownLeft),
+            Action::MoveDownRight => Some(Direction::DownRight),
+            _ => None,
+        }
+    }
+}
+
+/// A key, pressed with or without Ctrl, Alt or Shift.
+///
+/// Written as e.g. `w`, `Up`, `Esc`, `Ctrl+s` or `Shift+Left`. Shift is part of the character
+/// for letters, so `W` rather than `Shift+w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers.intersection(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Key { code, modifiers }
+    }
+
+    /// The key pressed, if `event` is a key press.
+    pub fn from_event(event: &GameEvent) -> Option<Key> {
+        match event {
+            GameEvent::Key(KeyEvent {
+                code, modifiers, ..
+            }) => Some(Key::new(*code, *modifiers)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = name;
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, name)),
+            };
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "esc" => KeyCode::Esc,
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                _ => return Err(format!("unknown key `{}`", name)),
+            },
+        };
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+/// Writes a key the way a key binding file would, e.g. `Ctrl+s` or `Shift+Left`.
+impl Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SHIFT, "Shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Which key does what.
+///
+/// A key binding file lists keys for some actions, e.g. `{ MoveUp: ["Up", "w"], Log: ["L"] }`,
+/// see `keys/wasd.ron`. Actions it leaves out keep their default keys, except for any key the
+/// file gives to something else.
+#[derive(Debug)]
+pub struct KeyBindings {
+    actions: HashMap<Key, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let defaults = [
+            (Action::MoveUp, "Up"),
+            (Action::MoveDown, "Down"),
+            (Action::MoveLeft, "Left"),
+            (Action::MoveRight, "Right"),
+            (Action::MoveUpLeft, "Home"),
+            (Action::MoveUpRight, "PageUp"),
+            (Action::MoveDownLeft, "End"),
+            (Action::MoveDownRight, "PageDown"),
+            (Action::RunUp, "Shift+Up"),
+            (Action::RunDown, "Shift+Down"),
+            (Action::RunLeft, "Shift+Left"),
+            (Action::RunRight, "Shift+Right"),
+            (Action::RunUpLeft, "Shift+Home"),
+            (Action::RunUpRight, "Shift+PageUp"),
+            (Action::RunDownLeft, "Shift+End"),
+            (Action::RunDownRight, "Shift+PageDown"),
+            (Action::Travel, "t"),
+            (Action::ShowQuest, "q"),
+            (Action::ResetQuest, "r"),
+            (Action::Undo, "u"),
+            (Action::Undo, "Ctrl+z"),
+            (Action::Redo, "Ctrl+r"),
+            (Action::Redo, "Ctrl+y"),
+            (Action::Save, "Ctrl+s"),
+            (Action::Inventory, "i"),
+            (Action::Use, "e"),
+            (Action::Minimap, "m"),
+            (Action::Log, "l"),
+            (Action::Close, "Esc"),
+        ];
+        KeyBindings {
+            actions: defaults
+                .into_iter()
+                .map(|(action, key)| (key.parse().unwrap(), action))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Reads a key binding file on top of the defaults.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: BTreeMap<Action, Vec<String>> =
+            ron::from_str(source).map_err(|err| err.to_string())?;
+        let mut bound = HashMap::new();
+        for (action, keys) in &file {
+            for name in keys {
+                let key = name.parse::<Key>()?;
+                if let Some(other) = bound.insert(key, *action) {
+                    return Err(format!(
+                        "`{}` is bound to both {:?} and {:?}",
+                        name, other, action
+                    ));
+                }
+            }
+        }
+        let rebound = file.keys().collect::<HashSet<_>>();
+        let mut actions = KeyBindings::default().actions;
+        actions.retain(|key, action| !rebound.contains(action) && !bound.contains_key(key));
+        actions.extend(bound);
+        Ok(KeyBindings { actions })
+    }
+
+    /// What `event` does, if it is a bound key.
+    pub fn action(&self, event: &GameEvent) -> Option<Action> {
+        self.actions.get(&Key::from_event(event)?).copied()
+    }
+
+    /// The keys that do `action`, shortest name first, e.g. `u` before `Ctrl+z`.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        let mut keys = self
+            .actions
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key.to_string())
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+        keys
+    }
+
+    /// The keys that do `action`, to tell the player about, e.g. `u or Ctrl+z`.
+    pub fn describe(&self, action: Action) -> String {
+        match self.keys(action).as_slice() {
+            [] => String::from("no key"),
+            keys => keys.join(" or "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keys::{Action, Key, KeyBindings};
+    use termgame::{GameEvent, KeyCode, KeyEvent, KeyModifiers};
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> GameEvent {
+        GameEvent::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(
+            Ok(Key::new(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+            "Ctrl+s".parse()
+        );
+        assert_eq!(
+            Ok(Key::new(KeyCode::Char('+'), KeyModifiers::NONE)),
+            "+".parse()
+        );
+        assert_eq!(
+            Ok(Key::new(KeyCode::Left, KeyModifiers::SHIFT)),
+            "shift+left".parse()
+        );
+        assert!("Hyper+x".parse::<Key>().is_err());
+        assert!("Upp".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn files_override_defaults() {
+        let keys = KeyBindings::parse(include_str!("../../keys/vim.ron")).unwrap();
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            Some(Action::MoveRight),
+            keys.action(&press(KeyCode::Char('l'), none))
+        );
+        assert_eq!(
+            Some(Action::MoveRight),
+            keys.action(&press(KeyCode::Right, none))
+        );
+        assert_eq!(
+            Some(Action::Log),
+            keys.action(&press(KeyCode::Char('L'), KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            Some(Action::ShowQuest),
+            keys.action(&press(KeyCode::Char('q'), none))
+        );
+        KeyBindings::parse(include_str!("../../keys/wasd.ron")).unwrap();
+
+        let clash = KeyBindings::parse(r#"{ MoveUp: ["w"], Save: ["w"] }"#);
+        assert!(clash.unwrap_err().contains("bound to both"));
+    }
+
+    #[test]
+    fn describe_keys() {
+        for name in ["Ctrl+s", "Shift+Left", "PageUp", "Esc", "Space", "L", "+"] {
+            assert_eq!(name, name.parse::<Key>().unwrap().to_string());
+        }
+        let keys = KeyBindings::parse(include_str!("../../keys/vim.ron")).unwrap();
+        assert_eq!(vec!["l", "Right"], keys.keys(Action::MoveRight));
+        assert_eq!("Ctrl+z", keys.describe(Action::Undo));
+        assert_eq!("u or Ctrl+z", KeyBindings::default().describe(Action::Undo));
+        assert_eq!("Esc or Ctrl+[", keys.describe(Action::Close));
+        let unbound = KeyBindings::parse(r#"{ Travel: [] }"#).unwrap();
+        assert_eq!("no key", unbound.describe(Action::Travel));
+    }
+}
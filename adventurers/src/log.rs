@@ -0,0 +1,119 @@
+use crate::keys::{Action, KeyBindings};
+use std::collections::VecDeque;
+use termgame::Message;
+
+/// How many entries are kept before the oldest are dropped.
+const CAPACITY: usize = 500;
+
+/// How many entries the history view shows at once.
+const PAGE: usize = 8;
+
+/// Everything that happened to the player: signs read, objects picked up, quest milestones
+/// and deaths. Opened with the log key, and scrolled with the keys for moving up and down.
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    entries: VecDeque<String>,
+    /// How far back from the newest entry the history view is scrolled, while it is open.
+    scroll: Option<usize>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, entry: impl Into<String>) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.into().replace('\n', " "));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.scroll.is_some()
+    }
+
+    /// Opens the history view at the newest entries, or closes it.
+    pub fn toggle(&mut self) {
+        self.scroll = match self.scroll {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    /// Scrolls the history view back by `lines`, or forward if negative.
+    pub fn scroll(&mut self, lines: isize) {
+        let oldest = self.entries.len().saturating_sub(PAGE);
+        if let Some(scroll) = self.scroll {
+            self.scroll = Some(scroll.saturating_add_signed(lines).min(oldest));
+        }
+    }
+
+    /// The history view, if it is open, naming the keys to use it with from `keys`.
+    pub fn view(&self, keys: &KeyBindings) -> Option<Message> {
+        let scroll = self.scroll?;
+        let close = keys.describe(Action::Log);
+        if self.entries.is_empty() {
+            return Some(
+                Message::new(String::from("Nothing has happened yet."))
+                    .title(format!("Log ({} to close)", close)),
+            );
+        }
+        let scroll_keys = [Action::MoveUp, Action::MoveDown].map(|action| {
+            keys.keys(action)
+                .first()
+                .cloned()
+                .unwrap_or(String::from("-"))
+        });
+        let end = self.entries.len() - scroll;
+        let start = end.saturating_sub(PAGE);
+        let text = self
+            .entries
+            .range(start..end)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(Message::new(text).title(format!(
+            "Log {}-{} of {} ({} to scroll, {} to close)",
+            start + 1,
+            end,
+            self.entries.len(),
+            scroll_keys.join("/"),
+            close
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keys::KeyBindings;
+    use crate::log::{MessageLog, PAGE};
+
+    #[test]
+    fn scrolls_through_history() {
+        let mut log = MessageLog::default();
+        for i in 0..20 {
+            log.push(format!("entry {}", i));
+        }
+        let keys = KeyBindings::default();
+        assert!(log.view(&keys).is_none());
+        log.toggle();
+        let newest = log.view(&keys).unwrap();
+        assert!(newest.text.ends_with("entry 19"));
+        assert_eq!(
+            Some("Log 13-20 of 20 (Up/Down to scroll, l to close)"),
+            newest.title.as_deref()
+        );
+        let vim = KeyBindings::parse(include_str!("../../keys/vim.ron")).unwrap();
+        assert_eq!(
+            Some("Log 13-20 of 20 (k/j to scroll, L to close)"),
+            log.view(&vim).unwrap().title.as_deref()
+        );
+
+        log.scroll(100);
+        let oldest = log.view(&keys).unwrap();
+        assert!(oldest.text.starts_with("entry 0\n"));
+        assert_eq!(PAGE, oldest.text.lines().count());
+
+        log.scroll(-100);
+        assert!(log.view(&keys).unwrap().text.ends_with("entry 19"));
+        log.toggle();
+        assert!(!log.is_open());
+    }
+}
@@ -1,129 +1,1225 @@
-use adventurers_quest::{OrderedQuest, QuestExt, QuestSystem, SubMemQuest, SubQuest};
+use adventurers_quest::{CloneQuest, OrderedQuest, QuestSystem, SubMemQuest, SubQuest};
+use ascii::AsciiMap;
 use block::Block;
+use camera::Camera;
+use chunks::{ChunkedMap, World};
+use clap::Parser;
+use cli::{Cli, Command, Format, GameArgs};
+use crossterm::event::{MouseButton, MouseEventKind};
+use dialogue::Npcs;
 use direction::Direction;
+use editor::Editor;
+use event::Event;
+use fog::Fog;
+use generator::GeneratorOptions;
+use hud::Hud;
+use keys::{Action, Key, KeyBindings};
+use map::{MapError, MapFile, MapMeta};
+use minimap::Minimap;
+use palette::Palette;
 use player::{Movement, Player};
-use std::collections::HashMap;
-use std::env;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use replay::{Recording, Step};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Duration;
 use termgame::{
-    run_game, Controller, Game, GameEvent, GameSettings, KeyCode, Message, SimpleEvent,
-    StyledCharacter,
+    run_game, Controller, Game, GameColor, GameEvent, GameSettings, GameStyle, KeyCode, KeyEvent,
+    Message, MouseEvent, SimpleEvent, StyledCharacter,
 };
+use tiled::TileTable;
+use undo::{History, Snapshot};
+use validate::{Problem, Severity};
+use viewport::{visible, Renderer};
 enum MyResult {
-    GameParam(HashMap<(i32, i32), Block>, Box<dyn QuestExt<Block>>),
+    GameParam(
+        Box<World>,
+        Box<dyn CloneQuest<Event>>,
+        Box<Recording>,
+        Option<PathBuf>,
+        KeyBindings,
+    ),
+    EditParam(PathBuf, Box<MapFile>, bool),
     MissingArguments,
     MapNotFound,
-    MapIncorrectFormat,
+    MapIncorrectFormat(String),
     QuestNotImplemented,
+    MapWritten(String),
+    MapNotWritten,
+    Validated(String, Vec<Problem>),
+    Solved(Vec<Direction>, Option<String>),
+    NoSolution,
+    RecordingNotLoaded(String),
+    KeysNotLoaded(String),
 }
 
+impl MyResult {
+    /// What the process exits with, see the help text in `cli.rs`.
+    fn exit_code(&self) -> u8 {
+        match self {
+            MyResult::GameParam(..)
+            | MyResult::EditParam(..)
+            | MyResult::MapWritten(_)
+            | MyResult::Solved(..) => 0,
+            MyResult::MissingArguments => 2,
+            MyResult::MapNotFound => 3,
+            MyResult::MapIncorrectFormat(_) => 4,
+            MyResult::QuestNotImplemented => 5,
+            MyResult::MapNotWritten => 6,
+            MyResult::Validated(_, problems)
+                if problems.iter().any(|p| p.severity == Severity::Error) =>
+            {
+                7
+            }
+            MyResult::Validated(..) => 0,
+            MyResult::NoSolution => 8,
+            MyResult::RecordingNotLoaded(_) => 9,
+            MyResult::KeysNotLoaded(_) => 10,
+        }
+    }
+}
+
+pub mod ascii;
 pub mod block;
+pub mod camera;
+pub mod chunks;
+pub mod cli;
+pub mod creatures;
+pub mod dialogue;
 pub mod direction;
+pub mod editor;
+pub mod event;
+pub mod fog;
+pub mod generator;
+pub mod hud;
+pub mod items;
+pub mod keys;
+pub mod log;
+pub mod map;
+pub mod minimap;
+pub mod palette;
+pub mod path;
 pub mod player;
+pub mod replay;
+pub mod solve;
+pub mod tiled;
+pub mod undo;
+pub mod validate;
+pub mod viewport;
+/// How many ticks pass between two moves of a replay.
+const REPLAY_TICKS: u32 = 4;
+
+/// How many ticks pass between two steps of travelling to a place.
+const TRAVEL_TICKS: u32 = 2;
+
+/// The most steps a single run takes, so running off the edge of the map ends.
+const MAX_RUN: usize = 500;
+
 pub struct MyGame {
     player: Player,
+    meta: MapMeta,
+    /// Every move made so far, written out at the end with `play --save`.
+    recording: Recording,
+    /// Steps still to be played back by `replay`.
+    replay: VecDeque<Step>,
+    /// Steps still to be taken to the place picked with the cursor or the mouse.
+    travel: VecDeque<Direction>,
+    /// Where the travel cursor is, while it is out.
+    cursor: Option<(i32, i32)>,
+    ticks: u32,
+    camera: Camera,
+    renderer: Renderer,
+    /// What the player has seen, with fog of war on.
+    fog: Option<Fog>,
+    /// Shown with `m`.
+    minimap: Option<Minimap>,
+    hud: Hud,
+    /// Steps that can be undone and redone.
+    history: History,
+    /// Whether the player is picking an item to use.
+    picking_item: bool,
+    keys: KeyBindings,
+    /// Where the recording is written, with `play --save`.
+    save: Option<PathBuf>,
+}
+
+impl MyGame {
+    fn move_player(&mut self, game: &mut Game, direction: Direction) {
+        let before = Snapshot::take(&self.player, self.recording.steps.len());
+        let lives = self.player.lives;
+        self.recording.steps.push(Step::Move(direction));
+        let changed = self.player.take_turn(game, direction);
+        if let Some(changed) = changed {
+            self.history.record(before, Step::Move(direction));
+            // Dying costs a life, and there is no undoing that.
+            if self.player.lives != lives {
+                self.history.clear();
+            }
+            self.history.forget_changes(&mut self.player);
+            self.uncover(game, changed.clone());
+            if let Some(minimap) = &mut self.minimap {
+                // Objects are picked up as the player leaves them behind.
+                minimap.refresh(
+                    &self.player.map,
+                    &self.player.palette,
+                    self.fog.as_ref(),
+                    changed,
+                );
+            }
+        }
+        self.render(game);
+        self.show_conversation(game);
+    }
+
+    /// Shows what the NPC being talked to is saying, if anyone.
+    fn show_conversation(&self, game: &mut Game) {
+        if let Some(conversation) = &self.player.conversation {
+            game.set_message(Some(conversation.view(&self.player.npcs, &self.keys)));
+        }
+    }
+
+    /// Answers the NPC with the number keys while a conversation is going on, until it ends or
+    /// the player walks away.
+    fn converse(&mut self, game: &mut Game, event: GameEvent) {
+        if let GameEvent::Resize(..) = event {
+            self.render(game);
+        }
+        let picked = match event {
+            GameEvent::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) if self.replay.is_empty() => c.to_digit(10).filter(|n| *n > 0),
+            _ => None,
+        };
+        let last_line = self.player.conversation.as_ref().is_some_and(|c| {
+            c.line(&self.player.npcs)
+                .is_some_and(|l| l.choices.is_empty())
+        });
+        let chosen = picked.is_some_and(|n| self.choose(game, n as usize - 1));
+        match self.keys.action(&event) {
+            _ if chosen => {}
+            Some(Action::Undo) if self.replay.is_empty() => self.undo(game),
+            Some(Action::Close) => self.player.conversation = None,
+            _ if last_line && Key::from_event(&event).is_some() && self.replay.is_empty() => {
+                self.player.conversation = None
+            }
+            _ => self.show_conversation(game),
+        }
+        self.draw_overlays(game, vec![]);
+    }
+
+    /// Goes back to how things were before the last move, unless playing hardcore.
+    fn undo(&mut self, game: &mut Game) {
+        let before = match self.history.undo() {
+            Some(before) => before,
+            None => {
+                let text = match self.meta.rules.hardcore {
+                    true => "There is no going back in hardcore",
+                    false => "Nothing to undo",
+                };
+                game.set_message(Some(Message::new(String::from(text))));
+                return;
+            }
+        };
+        self.recording.steps.truncate(before.steps);
+        self.go_back(game, before);
+    }
+
+    /// Takes the last step undone again.
+    fn redo(&mut self, game: &mut Game) {
+        match self.history.redo() {
+            Some(step) => self.take_step(game, step),
+            None => game.set_message(Some(Message::new(String::from("Nothing to redo")))),
+        }
+    }
+
+    /// Takes a recorded `step` as if its key had been pressed.
+    fn take_step(&mut self, game: &mut Game, step: Step) {
+        match step {
+            Step::Move(direction) => self.move_player(game, direction),
+            Step::Use(glyph) => self.use_item(game, glyph),
+            Step::Choose(index) => {
+                self.choose(game, index);
+            }
+        }
+    }
+
+    /// Answers the NPC with choice `index`, as a step that can be undone. Returns whether there
+    /// was such a choice.
+    fn choose(&mut self, game: &mut Game, index: usize) -> bool {
+        let before = Snapshot::take(&self.player, self.recording.steps.len());
+        if !self.player.choose(game, index) {
+            return false;
+        }
+        self.recording.steps.push(Step::Choose(index));
+        self.history.record(before, Step::Choose(index));
+        self.show_conversation(game);
+        true
+    }
+
+    /// Uses the item `glyph` from the bag, as a step that can be undone.
+    fn use_item(&mut self, game: &mut Game, glyph: char) {
+        let before = Snapshot::take(&self.player, self.recording.steps.len());
+        let changed = match self.player.use_item(game, glyph) {
+            Some(changed) => changed,
+            None => return self.draw_overlays(game, vec![]),
+        };
+        self.recording.steps.push(Step::Use(glyph));
+        self.history.record(before, Step::Use(glyph));
+        self.history.forget_changes(&mut self.player);
+        self.uncover(game, changed.clone());
+        // Broken rocks don't block the view anymore.
+        self.render(game);
+        self.draw_overlays(game, changed);
+    }
+
+    /// Puts the player back to how things were `before`, and draws what changed.
+    fn go_back(&mut self, game: &mut Game, before: Snapshot) {
+        let changed = before.restore(&mut self.player);
+        if let Some(minimap) = &mut self.minimap {
+            minimap.refresh(
+                &self.player.map,
+                &self.player.palette,
+                self.fog.as_ref(),
+                changed.iter().copied(),
+            );
+        }
+        self.uncover(game, changed);
+        self.render(game);
+        self.show_conversation(game);
+    }
+
+    /// Draws the creatures the player can see, under the minimap and status bar.
+    fn draw_creatures(&self, game: &mut Game) {
+        let covered = [
+            self.minimap.as_ref().and_then(|m| m.drawn()),
+            self.hud.drawn(),
+        ];
+        for creature in &self.player.creatures {
+            let (x, y) = creature.at;
+            let hidden = self.fog.as_ref().is_some_and(|fog| !fog.in_view((x, y)));
+            if hidden || covered.iter().flatten().any(|area| area.contains((x, y))) {
+                continue;
+            }
+            let styled = match game.get_screen_char(x, y) {
+                Some(tile) => tile.character(creature.glyph),
+                None => StyledCharacter::new(creature.glyph),
+            };
+            game.set_screen_char(x, y, Some(styled));
+        }
+    }
+
+    /// Moves in `direction` until blocked, or until stepping into water, onto a sign or an
+    /// object, or finishing part of the quest. Every step counts as a move of its own.
+    fn run(&mut self, game: &mut Game, direction: Direction) {
+        let milestones = self.player.milestones;
+        for _ in 0..MAX_RUN {
+            if self.player.run_blocked(direction) {
+                break;
+            }
+            self.move_player(game, direction);
+            if self.player.run_over(game, milestones) {
+                break;
+            }
+        }
+    }
+
+    /// Works out a path to `target`, to be walked along on the following ticks.
+    fn travel_to(&mut self, game: &mut Game, target: (i32, i32)) {
+        let (player, fog) = (&self.player, &self.fog);
+        // With fog of war on, the way has to go through places the player has seen.
+        let path = path::find_path(player, target, |p| match fog {
+            Some(fog) if !fog.seen(p) => None,
+            _ => player.breath_effect(p),
+        });
+        match path {
+            Some(path) => self.travel = path.into(),
+            None => game.set_message(Some(Message::new(String::from(
+                "There is no safe way there",
+            )))),
+        }
+    }
+
+    /// Takes the next step towards the place being travelled to. Travelling stops early if the
+    /// step is blocked or something is shown to the player.
+    fn travel_step(&mut self, game: &mut Game) {
+        if let Some(direction) = self.travel.pop_front() {
+            let from = (self.player.x, self.player.y);
+            self.move_player(game, direction);
+            if from == (self.player.x, self.player.y)
+                || self.player.died
+                || self.player.won
+                || game.get_message().is_some()
+            {
+                self.travel.clear();
+            }
+        }
+    }
+
+    /// Moves the travel cursor while it is out, until a place is picked or it is put away.
+    fn aim(&mut self, game: &mut Game, event: GameEvent) {
+        let cursor = match self.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        if let GameEvent::Resize(..) = event {
+            self.render(game);
+        }
+        let action = self.keys.action(&event);
+        if let Some(Action::Travel | Action::Close) = action {
+            self.cursor = None;
+            self.uncover(game, vec![cursor]);
+            if action == Some(Action::Travel) {
+                self.travel_to(game, cursor);
+            }
+            self.draw_overlays(game, vec![]);
+            return;
+        }
+        if let Some(next) = action.and_then(Action::direction).map(|d| d.step(cursor)) {
+            if visible(game).contains(next) {
+                self.cursor = Some(next);
+                self.uncover(game, vec![cursor]);
+            }
+        }
+        self.show_cursor(game);
+    }
+
+    /// Draws the travel cursor on top of everything else, with a hint on how to use it.
+    fn show_cursor(&mut self, game: &mut Game) {
+        let moves = [
+            Action::MoveUp,
+            Action::MoveLeft,
+            Action::MoveDown,
+            Action::MoveRight,
+        ]
+        .map(|action| {
+            self.keys
+                .keys(action)
+                .first()
+                .cloned()
+                .unwrap_or(String::from("-"))
+        });
+        let hint = format!(
+            "Move with {}, {} to go, {} to stay",
+            moves.join("/"),
+            self.keys.describe(Action::Travel),
+            self.keys.describe(Action::Close)
+        );
+        game.set_message(Some(Message::new(hint).title(String::from("Travel"))));
+        self.draw_overlays(game, vec![]);
+        if let Some((x, y)) = self.cursor {
+            let style = GameStyle::new()
+                .color(Some(GameColor::Black))
+                .background_color(Some(GameColor::Yellow));
+            game.set_screen_char(x, y, Some(StyledCharacter::new('X').style(style)));
+        }
+    }
+
+    /// Scrolls to the player, draws whatever came into view, and the player on top of it.
+    fn render(&mut self, game: &mut Game) {
+        self.camera.follow(game, (self.player.x, self.player.y));
+        let (fog, palette) = (&self.fog, &self.player.palette);
+        let error = self.renderer.update(game, &mut self.player.map, |map, p| {
+            tile_style(fog.as_ref(), palette, map, p)
+        });
+        if let Some(error) = error {
+            game.set_message(Some(
+                Message::new(error.to_string())
+                    .title(String::from("Part of the world could not be loaded")),
+            ));
+        }
+        let changed = self.look_around(game);
+        self.player.show_player(game);
+        self.draw_overlays(game, changed);
+    }
+
+    /// Updates what the player can see with fog of war on, and tells the quest about any new
+    /// part of the map explored. Returns the positions that came into or went out of sight.
+    fn look_around(&mut self, game: &mut Game) -> Vec<(i32, i32)> {
+        let fog = match &mut self.fog {
+            Some(fog) => fog,
+            None => return vec![],
+        };
+        let changed = fog.look(&self.player.map, (self.player.x, self.player.y));
+        let explored = fog.percent_explored(&self.player.map);
+        let (fog, palette) = (&self.fog, &self.player.palette);
+        self.renderer
+            .redraw(game, &self.player.map, changed.iter().copied(), |map, p| {
+                tile_style(fog.as_ref(), palette, map, p)
+            });
+        for percent in self.player.explored + 1..=explored {
+            if !self.player.won && !self.player.died {
+                self.player.register_event(game, &Event::Explored(percent));
+            }
+        }
+        self.player.explored = explored;
+        changed
+    }
+
+    /// Draws the minimap, if it is open, and the status bar on top of everything else. The
+    /// minimap cells holding `changed` are brought up to date first.
+    fn draw_overlays(&mut self, game: &mut Game, changed: Vec<(i32, i32)>) {
+        let player = (self.player.x, self.player.y);
+        let mut moved = vec![];
+        if let Some(minimap) = &mut self.minimap {
+            minimap.refresh(
+                &self.player.map,
+                &self.player.palette,
+                self.fog.as_ref(),
+                changed,
+            );
+            moved.extend(minimap.draw(game, player));
+        }
+        moved.extend(self.hud.draw(game, &hud::status_line(&self.player)));
+        let covered = [
+            self.minimap.as_ref().and_then(|m| m.drawn()),
+            self.hud.drawn(),
+        ];
+        let uncovered = moved
+            .iter()
+            .flat_map(|area| area.positions())
+            .filter(|p| !covered.iter().flatten().any(|area| area.contains(*p)))
+            .collect();
+        self.uncover(game, uncovered);
+        self.draw_creatures(game);
+    }
+
+    /// Scrolls through the message log while it is open, until it is closed again.
+    fn browse_log(&mut self, game: &mut Game, event: GameEvent) {
+        if let GameEvent::Resize(..) = event {
+            self.render(game);
+        }
+        match self.keys.action(&event) {
+            Some(Action::MoveUp) => self.player.log.scroll(1),
+            Some(Action::MoveDown) => self.player.log.scroll(-1),
+            Some(Action::Log | Action::Close) => self.player.log.toggle(),
+            _ => {}
+        }
+        game.set_message(self.player.log.view(&self.keys));
+        self.draw_overlays(game, vec![]);
+    }
+
+    /// Writes the recording so far to the `--save` file.
+    fn save(&mut self, game: &mut Game) {
+        let text = match &self.save {
+            Some(save) => match write_recording(&self.recording, save) {
+                true => format!("Game saved to {}", save.display()),
+                false => format!("Game could not be saved to {}", save.display()),
+            },
+            None => String::from("Start the game with --save <file> to be able to save it"),
+        };
+        game.set_message(Some(Message::new(text)));
+    }
+
+    fn show_inventory(&mut self, game: &mut Game) {
+        let text = match self.player.inventory.is_empty() {
+            true => String::from("Your bag is empty"),
+            false => self
+                .player
+                .inventory
+                .iter()
+                .map(|(c, count)| format!("'{}' x {}", c, count))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        game.set_message(Some(Message::new(text).title(String::from("Inventory"))));
+    }
+
+    /// The items in the bag that can be used, with how many of each there are.
+    fn usable_items(&self) -> Vec<(char, u32)> {
+        self.player
+            .inventory
+            .iter()
+            .filter(|(c, _)| items::find(&self.player.items, **c).is_some())
+            .map(|(c, count)| (*c, *count))
+            .collect()
+    }
+
+    /// Lists the items that can be used, numbered, to pick one with the number keys.
+    fn show_items(&mut self, game: &mut Game) {
+        let usable = self.usable_items();
+        if usable.is_empty() {
+            let text = String::from("You have nothing to use");
+            return game.set_message(Some(Message::new(text)));
+        }
+        let lines = usable
+            .iter()
+            .enumerate()
+            .map(|(i, (c, count))| {
+                let name = &items::find(&self.player.items, *c).unwrap().name;
+                format!("{}. {} ('{}' x {})", i + 1, name, c, count)
+            })
+            .collect::<Vec<_>>();
+        self.picking_item = true;
+        game.set_message(Some(
+            Message::new(lines.join("\n")).title(String::from("Use")),
+        ));
+    }
+
+    /// Uses the item picked with the number keys, or puts the list away on any other key.
+    fn pick_item(&mut self, game: &mut Game, event: GameEvent) {
+        if let GameEvent::Resize(..) = event {
+            self.render(game);
+            return self.show_items(game);
+        }
+        if Key::from_event(&event).is_none() {
+            return self.show_items(game);
+        }
+        self.picking_item = false;
+        let picked = match event {
+            GameEvent::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) => c.to_digit(10).filter(|n| *n > 0),
+            _ => None,
+        };
+        let glyph = picked.and_then(|n| self.usable_items().get(n as usize - 1).copied());
+        match glyph {
+            Some((glyph, _)) => self.use_item(game, glyph),
+            None => self.draw_overlays(game, vec![]),
+        }
+    }
+
+    /// Opens the minimap, or closes it and draws the map back where it was.
+    fn toggle_minimap(&mut self, game: &mut Game) {
+        match self.minimap.take() {
+            Some(minimap) => {
+                if let Some(drawn) = minimap.drawn() {
+                    self.uncover(game, drawn.positions().collect());
+                }
+            }
+            None => {
+                self.minimap =
+                    Minimap::new(&self.player.map, &self.player.palette, self.fog.as_ref());
+                self.draw_overlays(game, vec![]);
+            }
+        }
+    }
+
+    /// Draws the tiles at `positions` again, after something was drawn over them.
+    fn uncover(&mut self, game: &mut Game, positions: Vec<(i32, i32)>) {
+        let (fog, palette) = (&self.fog, &self.player.palette);
+        self.renderer
+            .redraw(game, &self.player.map, positions, |map, p| {
+                tile_style(fog.as_ref(), palette, map, p)
+            });
+        self.player.show_player(game);
+    }
+}
+
+/// How the tile at `position` is drawn, hidden or dimmed by the fog of war if there is one.
+fn tile_style(
+    fog: Option<&Fog>,
+    palette: &Palette,
+    map: &ChunkedMap,
+    position: (i32, i32),
+) -> Option<StyledCharacter> {
+    match fog {
+        Some(fog) => fog.style(map, palette, position),
+        None => map.get(&position).map(|block| palette.style(block)),
+    }
 }
 
 impl Controller for MyGame {
     fn on_start(&mut self, game: &mut Game) {
-        for (key, value) in &self.player.map {
-            game.set_screen_char(key.0, key.1, Some(StyledCharacter::from(value)));
+        self.render(game);
+        if let Some(title) = &self.meta.title {
+            let text = match &self.meta.author {
+                Some(author) => format!("by {}", author),
+                None => String::from("Good luck!"),
+            };
+            game.set_message(Some(Message::new(text).title(title.clone())));
         }
-        self.player.show_player(game);
     }
 
     fn on_event(&mut self, game: &mut Game, event: GameEvent) {
         game.set_message(None);
-        if self.player.died || self.player.won {
+        // Drowning can be undone, anything else after the game is over ends it.
+        let undoing = self.keys.action(&event) == Some(Action::Undo) && self.history.can_undo();
+        if (self.player.died && !undoing) || self.player.won {
             game.end_game();
         }
-        match event.into() {
-            SimpleEvent::Just(KeyCode::Up) => {
-                self.player.move_player(game, Direction::Up);
+        if self.player.log.is_open() {
+            self.browse_log(game, event);
+            return;
+        }
+        if self.cursor.is_some() {
+            self.aim(game, event);
+            return;
+        }
+        if self.player.conversation.is_some() {
+            self.converse(game, event);
+            return;
+        }
+        if self.picking_item {
+            self.pick_item(game, event);
+            return;
+        }
+        if let GameEvent::Resize(..) = event {
+            self.render(game);
+        }
+        // Pressing any key stops travelling.
+        if Key::from_event(&event).is_some() {
+            self.travel.clear();
+        }
+        let replaying = !self.replay.is_empty();
+        if let GameEvent::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            ..
+        }) = event
+        {
+            let terminal = crossterm::terminal::size().unwrap_or((80, 24));
+            if let Some(target) = viewport::tile_at(game, terminal, (column, row)) {
+                if !replaying {
+                    self.travel_to(game, target);
+                }
             }
-            SimpleEvent::Just(KeyCode::Down) => {
-                self.player.move_player(game, Direction::Down);
+        }
+        match self.keys.action(&event) {
+            Some(Action::MoveUp) if !replaying => self.move_player(game, Direction::Up),
+            Some(Action::MoveDown) if !replaying => self.move_player(game, Direction::Down),
+            Some(Action::MoveLeft) if !replaying => self.move_player(game, Direction::Left),
+            Some(Action::MoveRight) if !replaying => self.move_player(game, Direction::Right),
+            Some(Action::MoveUpLeft) if !replaying => self.move_player(game, Direction::UpLeft),
+            Some(Action::MoveUpRight) if !replaying => self.move_player(game, Direction::UpRight),
+            Some(Action::MoveDownLeft) if !replaying => self.move_player(game, Direction::DownLeft),
+            Some(Action::MoveDownRight) if !replaying => {
+                self.move_player(game, Direction::DownRight)
             }
-            SimpleEvent::Just(KeyCode::Left) => {
-                self.player.move_player(game, Direction::Left);
+            Some(Action::RunUp) if !replaying => self.run(game, Direction::Up),
+            Some(Action::RunDown) if !replaying => self.run(game, Direction::Down),
+            Some(Action::RunLeft) if !replaying => self.run(game, Direction::Left),
+            Some(Action::RunRight) if !replaying => self.run(game, Direction::Right),
+            Some(Action::RunUpLeft) if !replaying => self.run(game, Direction::UpLeft),
+            Some(Action::RunUpRight) if !replaying => self.run(game, Direction::UpRight),
+            Some(Action::RunDownLeft) if !replaying => self.run(game, Direction::DownLeft),
+            Some(Action::RunDownRight) if !replaying => self.run(game, Direction::DownRight),
+            Some(Action::Travel) if !replaying => {
+                self.cursor = Some((self.player.x, self.player.y));
+                self.show_cursor(game);
+                return;
             }
-            SimpleEvent::Just(KeyCode::Right) => {
-                self.player.move_player(game, Direction::Right);
+            Some(Action::ShowQuest) => {
+                game.set_message(Some(Message::new(self.player.quest.to_string())));
             }
-            SimpleEvent::Just(KeyCode::Char(c)) => {
-                if c == 'q' {
-                    game.set_message(Some(Message::new(self.player.quest.to_string())));
-                }
-                if c == 'r' {
-                    self.player.quest.reset();
-                }
+            Some(Action::ResetQuest) if !replaying => self.player.quest.reset(),
+            Some(Action::Undo) if !replaying => self.undo(game),
+            Some(Action::Redo) if !replaying => self.redo(game),
+            Some(Action::Save) => self.save(game),
+            Some(Action::Inventory) => self.show_inventory(game),
+            Some(Action::Use) if !replaying => self.show_items(game),
+            Some(Action::Minimap) => self.toggle_minimap(game),
+            Some(Action::Log) => {
+                self.player.log.toggle();
+                game.set_message(self.player.log.view(&self.keys));
             }
             _ => {}
         }
+        // Messages coming and going move the bottom of the screen.
+        self.draw_overlays(game, vec![]);
+    }
+
+    fn on_tick(&mut self, game: &mut Game) {
+        if self.player.died || self.player.won {
+            return;
+        }
+        self.ticks += 1;
+        if self.replay.is_empty() && self.travel.is_empty() {
+            return;
+        }
+        if !self.travel.is_empty() {
+            if self.ticks.is_multiple_of(TRAVEL_TICKS) {
+                self.travel_step(game);
+            }
+            return;
+        }
+        if self.ticks.is_multiple_of(REPLAY_TICKS) {
+            if let Some(step) = self.replay.pop_front() {
+                self.take_step(game, step);
+            }
+            if self.replay.is_empty() && game.get_message().is_none() {
+                game.set_message(Some(Message::new(String::from(
+                    "End of the replay, carry on from here!",
+                ))));
+            }
+        }
+    }
+}
+
+fn load_map(path: &Path) -> Result<MapFile, MyResult> {
+    if path.is_dir() {
+        return chunks::load_world(path).map_err(|err| map_error(path, err));
+    }
+    let file = File::open(path);
+    if file.is_err() {
+        return Err(MyResult::MapNotFound);
+    }
+    let mut source = String::new();
+    if let Err(err) = file.unwrap().read_to_string(&mut source) {
+        return Err(MyResult::MapIncorrectFormat(format!(
+            "{}: {}",
+            path.display(),
+            err
+        )));
+    }
+    let map = if path.to_string_lossy().ends_with(".json") {
+        tiled::import(&source, load_tile_table(&path.to_string_lossy())?)
+    } else {
+        MapFile::parse(&source)
+    };
+    map.map_err(|err| map_error(path, err))
+}
+
+fn map_error(path: &Path, err: MapError) -> MyResult {
+    match err {
+        MapError::Syntax(_) => MyResult::MapIncorrectFormat(format!("{}:{}", path.display(), err)),
+        MapError::Chunk(_) => MyResult::MapIncorrectFormat(err.to_string()),
+        _ => MyResult::MapIncorrectFormat(format!("{}: {}", path.display(), err)),
     }
-    fn on_tick(&mut self, _game: &mut Game) {}
 }
 
-fn prepare_launch(args: Vec<String>) -> MyResult {
-    if args.len() < 3 {
-        return MyResult::MissingArguments;
+/// A Tiled export `island.json` takes its tile ids from `island.blocks.ron`, if there is one.
+fn load_tile_table(path: &str) -> Result<TileTable, MyResult> {
+    let table_path = Path::new(path).with_extension("blocks.ron");
+    if !table_path.exists() {
+        return Ok(TileTable::default());
     }
-    let my_path = args.get(1).unwrap();
-    let my_quest = args.get(2).unwrap();
-    let file = File::open(my_path);
+    let file = File::open(&table_path);
     if file.is_err() {
-        return MyResult::MapNotFound;
-    }
-    let reader = BufReader::new(file.unwrap());
-    let map: Result<HashMap<(i32, i32), Block>, _> = ron::de::from_reader(reader);
-    if map.is_err() {
-        return MyResult::MapIncorrectFormat;
-    }
-    if my_quest == "q1" {
-        let quest1 = SubQuest::new(Block::Sand, 5, "Walk on Sand".to_string());
-        MyResult::GameParam(map.unwrap(), Box::new(quest1))
-    } else if my_quest == "q2" {
-        let quest2_1 = SubQuest::new(Block::Object('x'), 5, "Collect a 'x'".to_string());
-        let quest2_2 = SubQuest::new(Block::Object('y'), 3, "Collect a 'y'".to_string());
+        return Err(MyResult::MapNotFound);
+    }
+    ron::de::from_reader(file.unwrap())
+        .map_err(|err| MyResult::MapIncorrectFormat(format!("{}:{}", table_path.display(), err)))
+}
+
+/// What goes with the map at `path` from `<map>.<extension>` next to it, if there is one, like
+/// what its NPCs say in `<map>.npcs.ron`.
+fn load_sidecar<T: DeserializeOwned + Default>(
+    path: &Path,
+    extension: &str,
+) -> Result<T, MyResult> {
+    let sidecar_path = path.with_extension(extension);
+    if !sidecar_path.exists() {
+        return Ok(T::default());
+    }
+    let source = std::fs::read_to_string(&sidecar_path).map_err(|err| {
+        MyResult::MapIncorrectFormat(format!("{}: {}", sidecar_path.display(), err))
+    })?;
+    ron::from_str(&source)
+        .map_err(|err| MyResult::MapIncorrectFormat(format!("{}:{}", sidecar_path.display(), err)))
+}
+
+/// Builds one of the quests that can be chosen with `--quest`.
+fn make_quest(name: &str) -> Option<Box<dyn CloneQuest<Event>>> {
+    if name == "q1" {
+        let quest1 = SubQuest::new(Event::Tile(Block::Sand), 5, "Walk on Sand".to_string());
+        Some(Box::new(quest1))
+    } else if name == "q2" {
+        let quest2_1 = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            5,
+            "Collect a 'x'".to_string(),
+        );
+        let quest2_2 = SubQuest::new(
+            Event::Tile(Block::Object('y')),
+            3,
+            "Collect a 'y'".to_string(),
+        );
         let quest2 = OrderedQuest::new(vec![quest2_1, quest2_2]);
-        MyResult::GameParam(map.unwrap(), Box::new(quest2))
-    } else if my_quest == "q3" {
-        let quest3_1_1 = SubQuest::new(Block::Sand, 5, "Walk on Sand".to_string());
-        let quest3_1_2 = SubQuest::new(Block::Object('x'), 1, "Collect a 'x'".to_string());
+        Some(Box::new(quest2))
+    } else if name == "q3" {
+        let quest3_1_1 = SubQuest::new(Event::Tile(Block::Sand), 5, "Walk on Sand".to_string());
+        let quest3_1_2 = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            1,
+            "Collect a 'x'".to_string(),
+        );
         let quest3_1 = OrderedQuest::new(vec![quest3_1_1, quest3_1_2]);
-        let quest3_2_1 = SubQuest::new(Block::Object('y'), 1, "Collect a 'y'".to_string());
-        let quest3_2_2 = SubQuest::new(Block::Grass, 1, "Walk on Grass".to_string());
+        let quest3_2_1 = SubQuest::new(
+            Event::Tile(Block::Object('y')),
+            1,
+            "Collect a 'y'".to_string(),
+        );
+        let quest3_2_2 = SubQuest::new(Event::Tile(Block::Grass), 1, "Walk on Grass".to_string());
         let quest3_2 = OrderedQuest::new(vec![quest3_2_1, quest3_2_2]);
         let quest3_3 = SubMemQuest::new(
-            Block::Water,
+            Event::Tile(Block::Water),
             9,
             3,
             "Walk over exactly 9 blocks of water".to_string(),
         );
         let quest3 = QuestSystem::new(vec![quest3_1, quest3_2], vec![], vec![quest3_3], 2);
-        MyResult::GameParam(map.unwrap(), Box::new(quest3))
+        Some(Box::new(quest3))
+    } else if name == "q4" {
+        let quest4 = SubQuest::new(Event::Explored(50), 1, "Explore half the map".to_string());
+        Some(Box::new(quest4))
+    } else if name == "q5" {
+        let quest5_1 = SubQuest::new(
+            Event::Talked("Hermit".to_string()),
+            1,
+            "Talk to the Hermit".to_string(),
+        );
+        let quest5_2 = SubQuest::new(
+            Event::Chose("help".to_string()),
+            1,
+            "Agree to help the Hermit".to_string(),
+        );
+        let quest5_3 = SubQuest::new(
+            Event::Tile(Block::Flowerbush),
+            3,
+            "Tend the flowers".to_string(),
+        );
+        Some(Box::new(OrderedQuest::new(vec![
+            quest5_1, quest5_2, quest5_3,
+        ])))
+    } else if name == "q6" {
+        let quest6_1 = SubQuest::new(
+            Event::Defeated("Wolf".to_string()),
+            2,
+            "Defeat 2 wolves".to_string(),
+        );
+        let quest6_2 = SubQuest::new(
+            Event::Tile(Block::Object('f')),
+            2,
+            "Collect their fangs".to_string(),
+        );
+        Some(Box::new(OrderedQuest::new(vec![quest6_1, quest6_2])))
+    } else if name == "q7" {
+        let quest7_1 = SubQuest::new(
+            Event::Used("boat".to_string()),
+            1,
+            "Get in the boat".to_string(),
+        );
+        let quest7_2 = SubQuest::new(
+            Event::Used("pickaxe".to_string()),
+            1,
+            "Break the rocks".to_string(),
+        );
+        Some(Box::new(OrderedQuest::new(vec![quest7_1, quest7_2])))
+    } else {
+        None
+    }
+}
+
+/// The map `args` asks for, with its spawn point moved by `--spawn`, and the name of the quest
+/// to play on it.
+fn load_game(args: &GameArgs) -> Result<(MapFile, String), MyResult> {
+    let mut map = match (&args.map, args.seed) {
+        (Some(path), _) => load_map(path)?,
+        (None, Some(seed)) => generator::generate(&GeneratorOptions {
+            seed,
+            ..GeneratorOptions::default()
+        }),
+        (None, None) => return Err(MyResult::MissingArguments),
+    };
+    let quest = apply_args(args, &mut map.meta)?;
+    Ok((map, quest))
+}
+
+/// Like [`load_game`], but world directories are opened without reading all of their chunks.
+fn load_world(args: &GameArgs) -> Result<(World, String), MyResult> {
+    let (mut world, quest) = match &args.map {
+        Some(path) if path.is_dir() => {
+            let mut world = World::open(path).map_err(|err| map_error(path, err))?;
+            let quest = apply_args(args, &mut world.meta)?;
+            (world, quest)
+        }
+        _ => load_game(args).map(|(map, quest)| (map.into(), quest))?,
+    };
+    load_sidecars(args, &mut world)?;
+    Ok((world, quest))
+}
+
+/// Reads what the NPCs say, the creatures and the items from the files next to the map.
+fn load_sidecars(args: &GameArgs, world: &mut World) -> Result<(), MyResult> {
+    if let Some(path) = &args.map {
+        world.npcs = load_sidecar(path, "npcs.ron")?;
+        world.creatures = load_sidecar(path, "creatures.ron")?;
+        world.items = load_sidecar(path, "items.ron")?;
+    }
+    Ok(())
+}
+
+/// Moves the spawn point and changes the camera of a map as the flags ask, and works out
+/// which quest to play on it.
+fn apply_args(args: &GameArgs, meta: &mut MapMeta) -> Result<String, MyResult> {
+    if args.spawn.is_some() {
+        meta.spawn = args.spawn;
+    }
+    if args.camera.is_some() {
+        meta.camera = args.camera;
+    }
+    if args.sight.is_some() {
+        meta.rules.sight = args.sight;
+    }
+    if args.diagonal.is_some() {
+        meta.rules.diagonal = args.diagonal;
+    }
+    if args.undo.is_some() {
+        meta.rules.undo = args.undo;
+    }
+    meta.rules.hardcore |= args.hardcore;
+    if let Err(err) = meta.rules.check() {
+        let map = args
+            .map
+            .as_ref()
+            .map_or(String::from("map"), |p| p.display().to_string());
+        return Err(MyResult::MapIncorrectFormat(format!("{}: {}", map, err)));
+    }
+    match args.quest.as_ref().or(meta.quest.as_ref()) {
+        Some(quest) => Ok(quest.clone()),
+        None => Err(MyResult::MissingArguments),
+    }
+}
+
+/// `play` starts a game, which is recorded if `save` is given, with the key bindings in
+/// `keys` if given.
+fn prepare_launch(args: &GameArgs, save: Option<PathBuf>, keys: Option<&Path>) -> MyResult {
+    let keys = match load_keys(keys) {
+        Ok(keys) => keys,
+        Err(err) => return err,
+    };
+    let (world, quest_name) = match load_world(args) {
+        Ok(game) => game,
+        Err(err) => return err,
+    };
+    let quest = match make_quest(&quest_name) {
+        Some(quest) => quest,
+        None => return MyResult::QuestNotImplemented,
+    };
+    let recording = Recording {
+        map: args.map.clone(),
+        seed: args.seed,
+        quest: quest_name,
+        spawn: args.spawn,
+        sight: args.sight,
+        diagonal: args.diagonal,
+        creature_seed: rand::random(),
+        steps: vec![],
+    };
+    MyResult::GameParam(Box::new(world), quest, Box::new(recording), save, keys)
+}
+
+fn load_keys(path: Option<&Path>) -> Result<KeyBindings, MyResult> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(KeyBindings::default()),
+    };
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| MyResult::KeysNotLoaded(format!("{}: {}", path.display(), err)))?;
+    KeyBindings::parse(&source)
+        .map_err(|err| MyResult::KeysNotLoaded(format!("{}: {}", path.display(), err)))
+}
+
+fn write_recording(recording: &Recording, path: &Path) -> bool {
+    matches!(
+        recording.to_ron().map(|text| std::fs::write(path, text)),
+        Ok(Ok(()))
+    )
+}
+
+/// `replay <recording>` plays back a saved game.
+fn prepare_replay(path: &Path, keys: Option<&Path>) -> MyResult {
+    let recording: Recording = match std::fs::read_to_string(path) {
+        Ok(source) => match ron::from_str(&source) {
+            Ok(recording) => recording,
+            Err(err) => return MyResult::RecordingNotLoaded(format!("{}:{}", path.display(), err)),
+        },
+        Err(err) => return MyResult::RecordingNotLoaded(format!("{}: {}", path.display(), err)),
+    };
+    let args = GameArgs {
+        map: recording.map.clone(),
+        seed: recording.seed,
+        quest: Some(recording.quest.clone()),
+        spawn: recording.spawn,
+        camera: None,
+        sight: recording.sight,
+        diagonal: recording.diagonal,
+        undo: None,
+        hardcore: false,
+    };
+    match prepare_launch(&args, None, keys) {
+        MyResult::GameParam(map, quest, _, _, keys) => {
+            MyResult::GameParam(map, quest, Box::new(recording), None, keys)
+        }
+        err => err,
+    }
+}
+
+/// `solve` looks for moves that complete the quest, and checks them by playing them.
+fn solve_map(args: &GameArgs, save: Option<PathBuf>) -> MyResult {
+    // The search needs to see the whole map, so worlds are read with all of their chunks.
+    let (map, quest_name) = match load_game(args) {
+        Ok(game) => game,
+        Err(err) => return err,
+    };
+    let mut world = map.into();
+    if let Err(err) = load_sidecars(args, &mut world) {
+        return err;
+    }
+    let quest = match make_quest(&quest_name) {
+        Some(quest) => quest,
+        None => return MyResult::QuestNotImplemented,
+    };
+    // Creatures are always seeded the same, so a map has the same solution every time.
+    let creature_seed = 0;
+    let mut player = Player::new(world, quest);
+    player.rng = ChaCha8Rng::seed_from_u64(creature_seed);
+    let moves = match solve::solve(&player) {
+        Some(moves) => moves,
+        None => return MyResult::NoSolution,
+    };
+    replay::simulate(&mut player, &moves);
+    if !player.won {
+        return MyResult::NoSolution;
+    }
+    let save = match save {
+        Some(save) => save,
+        None => return MyResult::Solved(moves, None),
+    };
+    let recording = Recording {
+        map: args.map.clone(),
+        seed: args.seed,
+        quest: quest_name,
+        spawn: args.spawn,
+        sight: args.sight,
+        diagonal: args.diagonal,
+        creature_seed,
+        steps: moves.iter().copied().map(Step::Move).collect(),
+    };
+    match recording.to_ron().map(|text| std::fs::write(&save, text)) {
+        Ok(Ok(())) => MyResult::Solved(moves, Some(save.display().to_string())),
+        _ => MyResult::MapNotWritten,
+    }
+}
+
+/// `convert` rewrites a map as an ascii grid or as one `(x, y): Block` entry per tile.
+fn convert_map(path: &Path, save: &Path, format: Format) -> MyResult {
+    let map = match load_map(path) {
+        Ok(map) => map,
+        Err(err) => return err,
+    };
+    let output = match format {
+        Format::Tiles => map.to_ron(),
+        Format::Ascii => match AsciiMap::from_map_file(map) {
+            Ok(ascii) => ascii.to_ron(),
+            Err(err) => return map_error(path, err),
+        },
+    };
+    match output.map(|text| std::fs::write(save, text)) {
+        Ok(Ok(())) => MyResult::MapWritten(save.display().to_string()),
+        _ => MyResult::MapNotWritten,
+    }
+}
+
+/// `generate` writes a freshly generated map, as a single file or as a world directory.
+fn generate_map(options: GeneratorOptions, save: &Path, chunked: bool) -> MyResult {
+    let map = generator::generate(&options);
+    let written = if chunked {
+        chunks::save_world(&map, save).is_ok()
     } else {
-        MyResult::QuestNotImplemented
-    }
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
-    match prepare_launch(args) {
-        MyResult::GameParam(map, quest) => {
-            let player = Player {
-                x: 2,
-                y: 2,
-                map,
-                breath: 10,
-                died: false,
-                quest,
-                won: false,
+        matches!(
+            map.to_ron().map(|text| std::fs::write(save, text)),
+            Ok(Ok(()))
+        )
+    };
+    match written {
+        true => MyResult::MapWritten(save.display().to_string()),
+        false => MyResult::MapNotWritten,
+    }
+}
+
+/// `validate` checks a map for mistakes, without playing it.
+fn validate_map(path: &Path) -> MyResult {
+    let map = match load_map(path) {
+        Ok(map) => map,
+        Err(err) => return err,
+    };
+    let npcs: Npcs = match load_sidecar(path, "npcs.ron") {
+        Ok(npcs) => npcs,
+        Err(err) => return err,
+    };
+    MyResult::Validated(path.display().to_string(), validate::validate(&map, &npcs))
+}
+
+/// `edit` opens the map in the editor, or starts a new map if it doesn't exist yet.
+fn prepare_edit(path: PathBuf) -> MyResult {
+    if !path.exists() {
+        return MyResult::EditParam(path, Box::default(), false);
+    }
+    let map = match load_map(&path) {
+        Ok(map) => map,
+        Err(err) => return err,
+    };
+    let ascii = std::fs::read_to_string(&path)
+        .map(|source| ron::from_str::<AsciiMap>(&source).is_ok())
+        .unwrap_or(false);
+    MyResult::EditParam(path, Box::new(map), ascii)
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let result = match Cli::parse().command {
+        Command::Play { game, save, keys } => prepare_launch(&game, save, keys.as_deref()),
+        Command::Validate { map } => validate_map(&map),
+        Command::Edit { map } => prepare_edit(map),
+        Command::Solve { game, save } => solve_map(&game, save),
+        Command::Convert { map, save, format } => convert_map(&map, &save, format),
+        Command::Replay { recording, keys } => prepare_replay(&recording, keys.as_deref()),
+        Command::Generate {
+            seed,
+            save,
+            width,
+            height,
+            chunked,
+        } => generate_map(
+            GeneratorOptions {
+                seed,
+                width,
+                height,
+                ..GeneratorOptions::default()
+            },
+            &save,
+            chunked,
+        ),
+    };
+    let code = result.exit_code();
+    match result {
+        MyResult::GameParam(world, quest, recording, save, keys) => {
+            let meta = world.meta.clone();
+            let mut player = Player::new(*world, quest);
+            player.rng = ChaCha8Rng::seed_from_u64(recording.creature_seed);
+            let mut controller = MyGame {
+                player,
+                camera: Camera::new(meta.camera.unwrap_or_default()),
+                fog: meta.rules.sight.map(Fog::new),
+                history: History::new(match meta.rules.hardcore {
+                    true => 0,
+                    false => meta.rules.undo.unwrap_or(undo::DEFAULT_DEPTH),
+                }),
+                meta,
+                replay: recording.steps.iter().copied().collect(),
+                travel: VecDeque::new(),
+                cursor: None,
+                picking_item: false,
+                recording: Recording {
+                    steps: vec![],
+                    ..*recording
+                },
+                ticks: 0,
+                renderer: Renderer::default(),
+                minimap: None,
+                hud: Hud::default(),
+                keys,
+                save,
             };
-            let mut controller = MyGame { player };
 
             run_game(
                 &mut controller,
@@ -132,13 +1228,63 @@ fn main() -> Result<(), Box<dyn Error>> {
                     .quit_event(Some(SimpleEvent::WithControl(KeyCode::Char('c')).into())),
             )?;
             println!("Game Ended!");
+            if let Some(save) = &controller.save {
+                match write_recording(&controller.recording, save) {
+                    true => println!("Game recorded to {}", save.display()),
+                    false => {
+                        println!("Game could not be recorded");
+                        return Ok(ExitCode::from(MyResult::MapNotWritten.exit_code()));
+                    }
+                }
+            }
+        }
+        MyResult::EditParam(path, map, ascii) => {
+            let mut controller = Editor::new(path, *map, ascii);
+            run_game(
+                &mut controller,
+                GameSettings::new()
+                    .tick_duration(Duration::from_millis(50))
+                    .quit_event(Some(SimpleEvent::WithControl(KeyCode::Char('c')).into())),
+            )?;
+        }
+        MyResult::MissingArguments => {
+            println!("Missing arguments, the map doesn't name a quest so --quest is needed")
         }
-        MyResult::MissingArguments => println!("Missing arguments"),
         MyResult::MapNotFound => println!("Map not found"),
-        MyResult::MapIncorrectFormat => println!("Map is in incorrect format"),
+        MyResult::MapIncorrectFormat(err) => println!("Map is in incorrect format\n{}", err),
         MyResult::QuestNotImplemented => {
-            println!("Quest not supported, valid quests are q1,q2 and q3")
+            println!("Quest not supported, valid quests are q1, q2, q3, q4, q5, q6 and q7")
+        }
+        MyResult::MapWritten(path) => println!("Map written to {}", path),
+        MyResult::MapNotWritten => println!("Map could not be written"),
+        MyResult::Validated(path, problems) => {
+            for problem in &problems {
+                println!("{}: {}", path, problem);
+            }
+            let errors = problems
+                .iter()
+                .filter(|p| p.severity == Severity::Error)
+                .count();
+            println!(
+                "{}: {} error(s), {} warning(s)",
+                path,
+                errors,
+                problems.len() - errors
+            );
         }
+        MyResult::Solved(moves, save) => {
+            let steps: String = moves.iter().map(|d| d.symbol()).collect();
+            println!("Solved in {} moves: {}", moves.len(), steps);
+            if let Some(save) = save {
+                println!(
+                    "Solution saved to {}, watch it with `replay {}`",
+                    save, save
+                );
+            }
+        }
+        MyResult::NoSolution => println!("No solution found"),
+        MyResult::RecordingNotLoaded(err) => println!("Recording could not be loaded\n{}", err),
+        MyResult::KeysNotLoaded(err) => println!("Key bindings could not be loaded\n{}", err),
     }
-    Ok(())
+    Ok(ExitCode::from(code))
 }
@@ -0,0 +1,447 @@
+use crate::ascii::AsciiMap;
+use crate::block::Block;
+use crate::camera::CameraMode;
+use crate::direction::{can_step, directions, Corners, Direction};
+use crate::palette::{BreathEffect, Palette, TileType};
+use ron::extensions::Extensions;
+use ron::ser::PrettyConfig;
+use ron::Options;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+
+/// Where the player starts on maps that don't say otherwise.
+pub const DEFAULT_SPAWN: (i32, i32) = (2, 2);
+
+/// A map file: a header describing the map, the tiles of the map, and the custom tile
+/// types they may refer to.
+///
+/// ```text
+/// #![enable(implicit_some)]
+/// (
+///     meta: (
+///         title: "The Island",
+///         author: "Yuanda",
+///         spawn: (5, 3),
+///         quest: "q2",
+///         rules: (breath: 15),
+///     ),
+///     palette: [(name: "Lava", glyph: '^', bg: Red, breath: Drain)],
+///     tiles: { (0, 0): Barrier, (5, 3): Grass, (6, 3): Custom("Lava") },
+/// )
+/// ```
+///
+/// Older maps are just the bare `tiles` map, and maps may also be drawn as an [`AsciiMap`];
+/// both are still accepted by [`MapFile::parse`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MapFile {
+    #[serde(default)]
+    pub meta: MapMeta,
+    #[serde(default)]
+    pub palette: Vec<TileType>,
+    #[serde(deserialize_with = "unique_tiles")]
+    pub tiles: HashMap<(i32, i32), Block>,
+}
+
+/// The bare form of a map file: nothing but tiles.
+struct BareMap(HashMap<(i32, i32), Block>);
+
+impl<'de> Deserialize<'de> for BareMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        unique_tiles(deserializer).map(BareMap)
+    }
+}
+
+/// Reads the tiles of a map, rejecting any coordinate that is given twice.
+fn unique_tiles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<(i32, i32), Block>, D::Error> {
+    struct TilesVisitor;
+
+    impl<'de> Visitor<'de> for TilesVisitor {
+        type Value = HashMap<(i32, i32), Block>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a map from (x, y) coordinates to blocks")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let mut tiles = HashMap::new();
+            while let Some((position, block)) = access.next_entry::<(i32, i32), Block>()? {
+                if let Some(first) = tiles.insert(position, block) {
+                    return Err(serde::de::Error::custom(format!(
+                        "duplicate tile at {:?}, it was already {:?}",
+                        position, first
+                    )));
+                }
+            }
+            Ok(tiles)
+        }
+    }
+
+    deserializer.deserialize_map(TilesVisitor)
+}
+
+/// A rectangle of the map, with both corners included. Missing tiles inside the bounds of a
+/// map are empty ground the player can walk on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl Bounds {
+    /// Whether `(x, y)` is inside, corners and edges included.
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        self.min.0 <= x && x <= self.max.0 && self.min.1 <= y && y <= self.max.1
+    }
+
+    /// Every position inside, row by row.
+    pub fn positions(self) -> impl Iterator<Item = (i32, i32)> {
+        (self.min.1..=self.max.1).flat_map(move |y| (self.min.0..=self.max.0).map(move |x| (x, y)))
+    }
+}
+
+/// The header of a map file. Everything in it is optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn: Option<(i32, i32)>,
+    /// The quest played when none is given on the command line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraMode>,
+    #[serde(default)]
+    pub rules: Rules,
+}
+
+impl MapMeta {
+    pub fn spawn(&self) -> (i32, i32) {
+        self.spawn.unwrap_or(DEFAULT_SPAWN)
+    }
+
+    fn is_empty(&self) -> bool {
+        self == &MapMeta::default()
+    }
+}
+
+/// Rules of the game that a map can change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// How many tiles of water the player can cross before drowning.
+    #[serde(default = "default_breath")]
+    pub breath: i32,
+    /// How many tiles away the player can see. Without it there is no fog of war, and the
+    /// whole map is shown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sight: Option<i32>,
+    /// Lets the player move diagonally too, with this rule for squeezing past corners.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagonal: Option<Corners>,
+    /// How many moves can be undone, see [`DEFAULT_DEPTH`](crate::undo::DEFAULT_DEPTH).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<usize>,
+    /// Turns undo off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hardcore: bool,
+    /// How many times the player comes back to the last checkpoint after dying. Without it,
+    /// dying ends the game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lives: Option<u32>,
+    /// How much damage from creatures the player can take, see
+    /// [`DEFAULT_HEALTH`](crate::player::DEFAULT_HEALTH).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<i32>,
+    /// How hard the player hits, see [`DEFAULT_ATTACK`](crate::player::DEFAULT_ATTACK).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attack: Option<i32>,
+    /// How much of each hit the player shrugs off. Without it, none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defense: Option<i32>,
+}
+
+impl Rules {
+    /// What makes the rules impossible to play by, if anything.
+    pub fn check(&self) -> Result<(), String> {
+        if self.breath <= 0 {
+            return Err(format!(
+                "breath is {}, but has to be at least 1",
+                self.breath
+            ));
+        }
+        match self.health {
+            Some(health) if health <= 0 => {
+                Err(format!("health is {}, but has to be at least 1", health))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn default_breath() -> i32 {
+    10
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            breath: default_breath(),
+            sight: None,
+            diagonal: None,
+            undo: None,
+            hardcore: false,
+            lives: None,
+            health: None,
+            attack: None,
+            defense: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Syntax(ron::error::SpannedError),
+    Json(serde_json::Error),
+    Tiled(String),
+    UnknownGlyph {
+        glyph: char,
+        x: i32,
+        y: i32,
+    },
+    UnknownTileId {
+        id: u32,
+        x: i32,
+        y: i32,
+    },
+    /// A world directory, or one of its chunks, could not be read.
+    Chunk(String),
+    /// A map has more kinds of block than there are characters to draw them as a grid.
+    TooManyBlocks(usize),
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::Syntax(err) => write!(f, "{}", err),
+            MapError::Json(err) => write!(f, "{}", err),
+            MapError::Tiled(err) => write!(f, "{}", err),
+            MapError::Chunk(err) => write!(f, "{}", err),
+            MapError::TooManyBlocks(count) => write!(
+                f,
+                "{} kinds of block are too many to draw as a grid of characters",
+                count
+            ),
+            MapError::UnknownGlyph { glyph, x, y } => {
+                write!(f, "'{}' at ({}, {}) is not in the legend", glyph, x, y)
+            }
+            MapError::UnknownTileId { id, x, y } => {
+                write!(
+                    f,
+                    "tile id {} at ({}, {}) is not in the tile table",
+                    id, x, y
+                )
+            }
+        }
+    }
+}
+
+/// What [`MapFile::to_ron`] writes, with tiles sorted by coordinate.
+#[derive(Serialize)]
+struct SortedMapFile<'a> {
+    #[serde(skip_serializing_if = "MapMeta::is_empty")]
+    meta: &'a MapMeta,
+    palette: &'a Vec<TileType>,
+    tiles: BTreeMap<&'a (i32, i32), &'a Block>,
+}
+
+impl MapFile {
+    /// Parses any of the three map formats. Optional fields may be written without `Some`.
+    ///
+    /// When none of them fit, the error comes from the format that got furthest
+    /// into the file, as that is most likely the one the map was written in.
+    pub fn parse(source: &str) -> Result<MapFile, MapError> {
+        let ron = Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+        let full = match ron.from_str::<MapFile>(source) {
+            Ok(map) => return Ok(map),
+            Err(err) => err,
+        };
+        let ascii = match ron.from_str::<AsciiMap>(source) {
+            Ok(ascii) => return ascii.into_map_file(),
+            Err(err) => err,
+        };
+        let bare = match ron.from_str::<BareMap>(source) {
+            Ok(BareMap(tiles)) => {
+                return Ok(MapFile {
+                    meta: MapMeta::default(),
+                    palette: vec![],
+                    tiles,
+                })
+            }
+            Err(err) => err,
+        };
+        let furthest = [full, ascii, bare]
+            .into_iter()
+            .rev()
+            .max_by_key(|err| (err.position.line, err.position.col))
+            .unwrap();
+        Err(MapError::Syntax(furthest))
+    }
+
+    /// Writes the map out with one `(x, y): Block` entry per tile.
+    ///
+    /// Maps with only tiles are written in the bare form the original maps use.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        let tiles: BTreeMap<_, _> = self.tiles.iter().collect();
+        if self.palette.is_empty() && self.meta.is_empty() {
+            ron::ser::to_string_pretty(&tiles, PrettyConfig::default())
+        } else {
+            let map = SortedMapFile {
+                meta: &self.meta,
+                palette: &self.palette,
+                tiles,
+            };
+            ron::ser::to_string_pretty(&map, PrettyConfig::default())
+        }
+    }
+
+    /// The smallest rectangle holding every tile, or `None` for an empty map.
+    pub fn bounds(&self) -> Option<Bounds> {
+        Some(Bounds {
+            min: (
+                self.tiles.keys().map(|k| k.0).min()?,
+                self.tiles.keys().map(|k| k.1).min()?,
+            ),
+            max: (
+                self.tiles.keys().map(|k| k.0).max()?,
+                self.tiles.keys().map(|k| k.1).max()?,
+            ),
+        })
+    }
+
+    /// How stepping on a position affects breath, or `None` if it can't be walked on.
+    ///
+    /// Missing tiles are empty ground, it is up to the caller to keep to the
+    /// [`bounds`](Self::bounds).
+    pub fn breath_effect(&self, position: (i32, i32)) -> Option<BreathEffect> {
+        match self.tiles.get(&position) {
+            Some(Block::Barrier | Block::Npc(_)) => None,
+            Some(Block::Water) => Some(BreathEffect::Drain),
+            // Stopping to read a sign doesn't let the player catch their breath.
+            Some(Block::Sign(_)) => Some(BreathEffect::Hold),
+            Some(Block::Custom(name)) => match self.palette.iter().find(|t| &t.name == name) {
+                Some(tile) if !tile.walkable => None,
+                Some(tile) => Some(tile.breath),
+                None => Some(BreathEffect::Refill),
+            },
+            _ => Some(BreathEffect::Refill),
+        }
+    }
+
+    /// Every position of the map that can be walked to from `from`.
+    ///
+    /// Water (and custom tiles that drain breath) only count when `swim` is set,
+    /// since long stretches of it drown the player.
+    pub fn reachable(&self, from: (i32, i32), swim: bool) -> HashSet<(i32, i32)> {
+        match self.bounds() {
+            Some(bounds) => self.flood(from, swim, bounds),
+            None => HashSet::new(),
+        }
+    }
+
+    /// Splits the tiles that can be walked on into regions that can't be walked between,
+    /// as in [`reachable`](Self::reachable).
+    pub fn regions(&self, swim: bool) -> Vec<HashSet<(i32, i32)>> {
+        let bounds = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return vec![],
+        };
+        let mut positions: Vec<&(i32, i32)> = self.tiles.keys().collect();
+        positions.sort();
+        let mut seen: HashSet<(i32, i32)> = HashSet::new();
+        let mut regions = vec![];
+        for position in positions {
+            if !seen.contains(position) {
+                let region = self.flood(*position, swim, bounds);
+                if !region.is_empty() {
+                    seen.extend(&region);
+                    regions.push(region);
+                }
+            }
+        }
+        regions
+    }
+
+    /// The steps the rules allow from `from`, and where each one ends up. Whether the tile
+    /// stepped onto can be walked on is up to the caller.
+    pub fn steps(&self, from: (i32, i32)) -> impl Iterator<Item = (Direction, (i32, i32))> + '_ {
+        let diagonal = self.meta.rules.diagonal;
+        directions(diagonal)
+            .iter()
+            .filter(move |direction| {
+                can_step(diagonal, from, **direction, |p| {
+                    self.breath_effect(p).is_some()
+                })
+            })
+            .map(move |direction| (*direction, direction.step(from)))
+    }
+
+    fn flood(&self, from: (i32, i32), swim: bool, bounds: Bounds) -> HashSet<(i32, i32)> {
+        let passable = |pos: &(i32, i32)| {
+            bounds.contains(*pos)
+                && match self.breath_effect(*pos) {
+                    None => false,
+                    Some(BreathEffect::Drain) => swim,
+                    Some(_) => true,
+                }
+        };
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        if passable(&from) {
+            seen.insert(from);
+            queue.push_back(from);
+        }
+        while let Some(position) = queue.pop_front() {
+            for (_, next) in self.steps(position) {
+                if passable(&next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
+    pub fn into_parts(self) -> (HashMap<(i32, i32), Block>, Palette) {
+        (self.tiles, Palette::new(self.palette))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::map::MapFile;
+    use crate::palette::BreathEffect;
+
+    #[test]
+    fn bare_map() {
+        let map = MapFile::parse("{ (0, 0): Grass, (1, 0): Sign(\"hi\") }").unwrap();
+        assert!(map.palette.is_empty());
+        assert_eq!(Some(&Block::Grass), map.tiles.get(&(0, 0)));
+    }
+
+    #[test]
+    fn map_with_palette() {
+        let map = MapFile::parse(include_str!("../../maps/custom_tiles.ron")).unwrap();
+        let (tiles, palette) = map.into_parts();
+        assert_eq!(Some(&Block::Custom("Lava".to_string())), tiles.get(&(3, 2)));
+        let lava = palette.get("Lava").unwrap();
+        assert_eq!('^', lava.glyph);
+        assert_eq!(BreathEffect::Drain, lava.breath);
+        assert!(!palette.get("Wall").unwrap().walkable);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::block::Block;
+use crate::chunks::ChunkedMap;
+use crate::fog::Fog;
+use crate::map::Bounds;
+use crate::palette::Palette;
+use crate::viewport::visible;
+use std::collections::HashMap;
+use termgame::{Game, GameColor, GameStyle, StyledCharacter};
+
+/// How many cells across and down the minimap is, at most.
+const WIDTH: i32 = 24;
+const HEIGHT: i32 = 8;
+
+/// One cell of the minimap, standing for a block of tiles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Cell {
+    /// The colour most of the tiles are.
+    color: Option<GameColor>,
+    /// An object or sign somewhere in the tiles.
+    marker: Option<char>,
+}
+
+/// A small picture of the whole map, drawn over a corner of the screen.
+///
+/// Each cell stands for a block of tiles, coloured like most of them, and marks any object
+/// (with its own character) or sign (with `!`) in them. Only the part of the map loaded when
+/// the minimap is opened is shown, and with fog of war only the tiles seen so far.
+pub struct Minimap {
+    /// The part of the map shown.
+    bounds: Bounds,
+    /// How many tiles across and down each cell stands for.
+    scale: (i32, i32),
+    cells: HashMap<(i32, i32), Cell>,
+    /// Where on screen it was last drawn.
+    drawn: Option<Bounds>,
+}
+
+impl Minimap {
+    pub fn new(map: &ChunkedMap, palette: &Palette, fog: Option<&Fog>) -> Option<Self> {
+        let bounds = map.bounds()?;
+        let width = bounds.max.0 - bounds.min.0 + 1;
+        let height = bounds.max.1 - bounds.min.1 + 1;
+        let mut minimap = Minimap {
+            bounds,
+            scale: ((width + WIDTH - 1) / WIDTH, (height + HEIGHT - 1) / HEIGHT),
+            cells: HashMap::new(),
+            drawn: None,
+        };
+        let cells = minimap.area().positions().collect::<Vec<_>>();
+        for cell in cells {
+            minimap.refresh_cell(map, palette, fog, cell);
+        }
+        Some(minimap)
+    }
+
+    /// The cells, from `(0, 0)` in the top left.
+    fn area(&self) -> Bounds {
+        Bounds {
+            min: (0, 0),
+            max: (
+                (self.bounds.max.0 - self.bounds.min.0) / self.scale.0,
+                (self.bounds.max.1 - self.bounds.min.1) / self.scale.1,
+            ),
+        }
+    }
+
+    /// The cell standing for `position`.
+    fn cell_of(&self, (x, y): (i32, i32)) -> Option<(i32, i32)> {
+        if !self.bounds.contains((x, y)) {
+            return None;
+        }
+        Some((
+            (x - self.bounds.min.0) / self.scale.0,
+            (y - self.bounds.min.1) / self.scale.1,
+        ))
+    }
+
+    /// Works out again how the cells holding `positions` look, after objects were picked up
+    /// or more of the map was seen.
+    pub fn refresh(
+        &mut self,
+        map: &ChunkedMap,
+        palette: &Palette,
+        fog: Option<&Fog>,
+        positions: impl IntoIterator<Item = (i32, i32)>,
+    ) {
+        let mut cells = positions
+            .into_iter()
+            .filter_map(|position| self.cell_of(position))
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        cells.dedup();
+        for cell in cells {
+            self.refresh_cell(map, palette, fog, cell);
+        }
+    }
+
+    fn refresh_cell(
+        &mut self,
+        map: &ChunkedMap,
+        palette: &Palette,
+        fog: Option<&Fog>,
+        (cx, cy): (i32, i32),
+    ) {
+        let tiles = Bounds {
+            min: (
+                self.bounds.min.0 + cx * self.scale.0,
+                self.bounds.min.1 + cy * self.scale.1,
+            ),
+            max: (
+                self.bounds.min.0 + (cx + 1) * self.scale.0 - 1,
+                self.bounds.min.1 + (cy + 1) * self.scale.1 - 1,
+            ),
+        };
+        let mut colors: Vec<(Option<GameColor>, usize)> = vec![];
+        let mut cell = Cell::default();
+        for position in tiles.positions() {
+            if fog.is_some_and(|fog| !fog.seen(position)) {
+                continue;
+            }
+            let block = match map.get(&position) {
+                Some(block) => block,
+                None => continue,
+            };
+            match block {
+                Block::Object(c) => cell.marker = Some(*c),
+                Block::Sign(_) if cell.marker.is_none() => cell.marker = Some('!'),
+                Block::Checkpoint if cell.marker.is_none() => cell.marker = Some('⚑'),
+                Block::Npc(_) if cell.marker.is_none() => cell.marker = Some('☺'),
+                _ => {}
+            }
+            let color = palette.color(block);
+            match colors.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, count)) => *count += 1,
+                None => colors.push((color, 1)),
+            }
+        }
+        cell.color = colors
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .and_then(|(color, _)| color);
+        self.cells.insert((cx, cy), cell);
+    }
+
+    /// Where on screen the minimap would cover, in the top right corner, or the bottom left
+    /// one if the player is in the way.
+    fn placement(&self, game: &Game, player: (i32, i32)) -> Bounds {
+        let screen = visible(game);
+        let (width, height) = (self.area().max.0 + 1, self.area().max.1 + 1);
+        let top_right = Bounds {
+            min: (screen.max.0 - width + 1, screen.min.1),
+            max: (screen.max.0, screen.min.1 + height - 1),
+        };
+        if !top_right.contains(player) {
+            return top_right;
+        }
+        Bounds {
+            min: (screen.min.0, screen.max.1 - height + 1),
+            max: (screen.min.0 + width - 1, screen.max.1),
+        }
+    }
+
+    /// Draws the minimap over the screen, with the player on it. Returns where it was drawn
+    /// before, which has to be drawn over again if it moved.
+    pub fn draw(&mut self, game: &mut Game, player: (i32, i32)) -> Option<Bounds> {
+        let placement = self.placement(game, player);
+        let player_cell = self.cell_of(player);
+        for cell in self.area().positions() {
+            let Cell { color, marker } = self.cells.get(&cell).copied().unwrap_or_default();
+            let glyph = match player_cell == Some(cell) {
+                true => '♟',
+                false => marker.unwrap_or(' '),
+            };
+            let style = GameStyle::new()
+                .color(Some(GameColor::White))
+                .background_color(Some(color.unwrap_or(GameColor::Black)));
+            game.set_screen_char(
+                placement.min.0 + cell.0,
+                placement.min.1 + cell.1,
+                Some(StyledCharacter::new(glyph).style(style)),
+            );
+        }
+        let before = self.drawn.replace(placement);
+        before.filter(|before| *before != placement)
+    }
+
+    /// Where on screen the minimap was last drawn.
+    pub fn drawn(&self) -> Option<Bounds> {
+        self.drawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::chunks::ChunkedMap;
+    use crate::minimap::{Cell, Minimap};
+    use crate::palette::Palette;
+    use std::collections::HashMap;
+    use termgame::GameColor;
+
+    #[test]
+    fn downsamples_and_marks_objects() {
+        let mut tiles = (0..48)
+            .flat_map(|x| (0..16).map(move |y| ((x, y), Block::Grass)))
+            .collect::<HashMap<_, _>>();
+        tiles.insert((1, 1), Block::Water);
+        tiles.insert((5, 3), Block::Object('x'));
+        let mut map = ChunkedMap::new(tiles);
+        let palette = Palette::new(vec![]);
+        let mut minimap = Minimap::new(&map, &palette, None).unwrap();
+        assert_eq!((2, 2), minimap.scale);
+        assert_eq!((23, 7), minimap.area().max);
+        assert_eq!(
+            Cell {
+                color: Some(GameColor::Green),
+                marker: None
+            },
+            minimap.cells[&(0, 0)]
+        );
+        assert_eq!(Some('x'), minimap.cells[&(2, 1)].marker);
+
+        map.remove(&(5, 3));
+        minimap.refresh(&map, &palette, None, [(5, 3)]);
+        assert_eq!(None, minimap.cells[&(2, 1)].marker);
+    }
+}
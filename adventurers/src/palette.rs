@@ -0,0 +1,145 @@
+use crate::block::Block;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use termgame::{Font, GameColor, GameStyle, StyledCharacter};
+
+/// Colors a map file may use for custom tiles.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+}
+
+impl From<Color> for GameColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => GameColor::Black,
+            Color::Red => GameColor::Red,
+            Color::Green => GameColor::Green,
+            Color::Yellow => GameColor::Yellow,
+            Color::Blue => GameColor::Blue,
+            Color::Magenta => GameColor::Magenta,
+            Color::Cyan => GameColor::Cyan,
+            Color::Gray => GameColor::Gray,
+            Color::DarkGray => GameColor::DarkGray,
+            Color::LightRed => GameColor::LightRed,
+            Color::LightGreen => GameColor::LightGreen,
+            Color::LightYellow => GameColor::LightYellow,
+            Color::LightBlue => GameColor::LightBlue,
+            Color::LightMagenta => GameColor::LightMagenta,
+            Color::LightCyan => GameColor::LightCyan,
+            Color::White => GameColor::White,
+        }
+    }
+}
+
+/// What standing on a tile does to the player's breath.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum BreathEffect {
+    /// Breath is restored, like on dry land
+    #[default]
+    Refill,
+    /// Breath neither drains nor refills
+    Hold,
+    /// Breath drains, like in water
+    Drain,
+}
+
+/// A tile type declared in the palette of a map file, placed with `Custom("name")`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileType {
+    pub name: String,
+    #[serde(default = "default_glyph")]
+    pub glyph: char,
+    #[serde(default)]
+    pub fg: Option<Color>,
+    #[serde(default)]
+    pub bg: Option<Color>,
+    #[serde(default = "default_walkable")]
+    pub walkable: bool,
+    #[serde(default)]
+    pub breath: BreathEffect,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn default_glyph() -> char {
+    ' '
+}
+
+fn default_walkable() -> bool {
+    true
+}
+
+/// The set of custom tile types a map declares, keyed by name.
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    tiles: HashMap<String, TileType>,
+}
+
+impl Palette {
+    pub fn new(tiles: Vec<TileType>) -> Self {
+        Palette {
+            tiles: tiles.into_iter().map(|t| (t.name.clone(), t)).collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TileType> {
+        self.tiles.get(name)
+    }
+
+    /// Styles built-in blocks as usual, and custom blocks from their palette entry.
+    pub fn style(&self, block: &Block) -> StyledCharacter {
+        match block {
+            Block::Custom(name) => match self.get(name) {
+                Some(tile) => StyledCharacter::new(tile.glyph).style(
+                    GameStyle::new()
+                        .color(tile.fg.map(GameColor::from))
+                        .background_color(tile.bg.map(GameColor::from)),
+                ),
+                None => StyledCharacter::from(block),
+            },
+            _ => StyledCharacter::from(block),
+        }
+    }
+
+    /// The colour a block is drawn in, from its palette entry for custom blocks.
+    pub fn color(&self, block: &Block) -> Option<GameColor> {
+        match block {
+            Block::Custom(name) => self.get(name).and_then(|tile| tile.bg).map(GameColor::from),
+            _ => block.color(),
+        }
+    }
+
+    /// How a block looks once it is out of sight: terrain as a shaded square of its colour,
+    /// anything drawn as a character in dark gray.
+    pub fn dimmed(&self, block: &Block) -> StyledCharacter {
+        let (glyph, color) = match block {
+            Block::Custom(name) => match self.get(name) {
+                Some(tile) if tile.glyph == ' ' => ('░', tile.bg.map(GameColor::from)),
+                Some(tile) => (tile.glyph, Some(GameColor::DarkGray)),
+                None => ('?', Some(GameColor::DarkGray)),
+            },
+            Block::Sign(_) => ('💬', Some(GameColor::DarkGray)),
+            Block::Object(c) => (*c, Some(GameColor::DarkGray)),
+            Block::Checkpoint => ('⚑', Some(GameColor::DarkGray)),
+            Block::Npc(_) => ('☺', Some(GameColor::DarkGray)),
+            _ => ('░', self.color(block)),
+        };
+        StyledCharacter::new(glyph).style(GameStyle::new().color(color).font(Some(Font::DIM)))
+    }
+}
@@ -0,0 +1,162 @@
+use crate::direction::{can_step, directions, Direction};
+use crate::map::Bounds;
+use crate::palette::BreathEffect;
+use crate::player::Player;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Gives up after looking at this many states, when the target can't be reached.
+const MAX_SEARCH: usize = 200_000;
+
+/// A position, and the breath the player has left there.
+type State = ((i32, i32), i32);
+
+/// The shortest way for `player` to walk to `to`, with A*.
+///
+/// `tile` tells how stepping on a position affects breath, or `None` if it can't be walked
+/// on. Paths never run out of breath, so long stretches of water are walked around. The
+/// search keeps within the loaded part of the map, plus a tile of empty ground around it.
+pub fn find_path(
+    player: &Player,
+    to: (i32, i32),
+    tile: impl Fn((i32, i32)) -> Option<BreathEffect>,
+) -> Option<Vec<Direction>> {
+    let from = (player.x, player.y);
+    let area = search_area(player.map.bounds(), from, to);
+    let estimate = |(x, y): (i32, i32)| {
+        let (dx, dy) = ((x - to.0).abs(), (y - to.1).abs());
+        match player.diagonal {
+            Some(_) => dx.max(dy),
+            None => dx + dy,
+        }
+    };
+    let start = (from, player.breath);
+    // Each state reached, with the state and step it was first reached by.
+    let mut parents: HashMap<State, (State, Direction)> = HashMap::new();
+    let mut costs = HashMap::from([(start, 0)]);
+    let mut open = BinaryHeap::from([Reverse((estimate(from), 0, start))]);
+    while let Some(Reverse((_, cost, state @ (position, breath)))) = open.pop() {
+        if position == to {
+            return Some(path_to(&parents, start, state));
+        }
+        if costs.len() > MAX_SEARCH {
+            return None;
+        }
+        if costs.get(&state).is_some_and(|best| *best < cost) {
+            continue;
+        }
+        for &direction in directions(player.diagonal) {
+            if !can_step(player.diagonal, position, direction, |p| tile(p).is_some()) {
+                continue;
+            }
+            let next = direction.step(position);
+            if !area.contains(next) {
+                continue;
+            }
+            let left = match tile(next) {
+                None => continue,
+                Some(BreathEffect::Refill) => player.max_breath,
+                Some(BreathEffect::Hold) => breath,
+                Some(BreathEffect::Drain) => breath - 1,
+            };
+            let reached = (next, left);
+            if left <= 0 || costs.get(&reached).is_some_and(|best| *best <= cost + 1) {
+                continue;
+            }
+            costs.insert(reached, cost + 1);
+            parents.insert(reached, (state, direction));
+            open.push(Reverse((cost + 1 + estimate(next), cost + 1, reached)));
+        }
+    }
+    None
+}
+
+/// The loaded part of the map, grown to take in both ends of the path and a tile around it.
+fn search_area(loaded: Option<Bounds>, from: (i32, i32), to: (i32, i32)) -> Bounds {
+    let Bounds { min, max } = loaded.unwrap_or(Bounds {
+        min: from,
+        max: from,
+    });
+    Bounds {
+        min: (
+            min.0.min(from.0).min(to.0) - 1,
+            min.1.min(from.1).min(to.1) - 1,
+        ),
+        max: (
+            max.0.max(from.0).max(to.0) + 1,
+            max.1.max(from.1).max(to.1) + 1,
+        ),
+    }
+}
+
+fn path_to(
+    parents: &HashMap<State, (State, Direction)>,
+    start: State,
+    end: State,
+) -> Vec<Direction> {
+    let mut path = vec![];
+    let mut state = end;
+    while state != start {
+        let (parent, direction) = parents[&state];
+        path.push(direction);
+        state = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::direction::Corners;
+    use crate::event::Event;
+    use crate::map::MapFile;
+    use crate::path::find_path;
+    use crate::player::Player;
+    use crate::replay::simulate;
+    use adventurers_quest::SubQuest;
+
+    fn player(source: &str) -> Player {
+        let map = MapFile::parse(source).unwrap();
+        let quest = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            5,
+            "Collect a 'x'".to_string(),
+        );
+        Player::new(map.into(), Box::new(quest))
+    }
+
+    #[test]
+    fn walks_around_barriers_and_deep_water() {
+        // A wall with a gap at the bottom, and a pool too wide to swim straight across.
+        let mut source = String::from("{ (2, 2): Grass, (4, 1): Barrier, (4, 2): Barrier, ");
+        source.push_str("(4, 3): Barrier, (6, 3): Grass, ");
+        for x in 8..20 {
+            source.push_str(&format!("({}, 3): Water, ", x));
+        }
+        source.push_str("(20, 3): Sand }");
+        let mut player = player(&source);
+        let path = find_path(&player, (6, 3), |p| player.breath_effect(p)).unwrap();
+        assert_eq!(7, path.len());
+        simulate(&mut player, &path);
+        assert_eq!((6, 3), (player.x, player.y));
+
+        let across = find_path(&player, (20, 3), |p| player.breath_effect(p)).unwrap();
+        assert!(across.len() > 14);
+        simulate(&mut player, &across);
+        assert_eq!((20, 3), (player.x, player.y));
+        assert!(!player.died);
+    }
+
+    #[test]
+    fn diagonal_paths_and_dead_ends() {
+        let mut player = player("{ (2, 2): Grass, (5, 5): Sand, (9, 9): Barrier }");
+        player.diagonal = Some(Corners::Clear);
+        let path = find_path(&player, (5, 5), |p| player.breath_effect(p)).unwrap();
+        assert_eq!(3, path.len());
+        assert_eq!(
+            None,
+            find_path(&player, (9, 9), |p| player.breath_effect(p))
+        );
+    }
+}
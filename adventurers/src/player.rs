@@ -1,17 +1,75 @@
-use crate::direction::Direction;
+use crate::chunks::{ChunkedMap, World};
+use crate::creatures::{damage, Act, Creature};
+use crate::dialogue::{Conversation, Npcs};
+use crate::direction::{can_step, Corners, Direction};
+use crate::event::Event;
+use crate::items::{self, Effect, Items};
+use crate::log::MessageLog;
+use crate::palette::{BreathEffect, Palette};
+use crate::undo::{ChangeLog, Snapshot};
 use crate::Block;
-use adventurers_quest::{QuestExt, QuestStatus};
-use std::collections::HashMap;
-use termgame::{Game, Message, StyledCharacter, ViewportLocation};
+use adventurers_quest::{CloneQuest, QuestStatus};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, HashMap};
+use termgame::{Game, Message, StyledCharacter};
 
+/// How much damage the player can take, unless the map's rules say otherwise.
+pub const DEFAULT_HEALTH: i32 = 10;
+
+/// How hard the player hits, unless the map's rules say otherwise.
+pub const DEFAULT_ATTACK: i32 = 1;
+
+#[derive(Clone)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
-    pub map: HashMap<(i32, i32), Block>,
+    pub map: ChunkedMap,
+    pub palette: Palette,
+    /// What the NPCs on the map say.
+    pub npcs: Npcs,
+    /// The conversation going on with an NPC, if any.
+    pub conversation: Option<Conversation>,
     pub breath: i32,
+    pub max_breath: i32,
+    pub health: i32,
+    pub max_health: i32,
+    pub attack: i32,
+    pub defense: i32,
     pub died: bool,
-    pub quest: Box<dyn QuestExt<Block>>,
+    pub quest: Box<dyn CloneQuest<Event>>,
     pub won: bool,
+    /// How many of each object the player has picked up.
+    pub inventory: BTreeMap<char, u32>,
+    pub log: MessageLog,
+    /// Whether the player can move diagonally, and past which corners.
+    pub diagonal: Option<Corners>,
+    /// How many parts of the quest have been finished.
+    pub milestones: usize,
+    /// How many more times the player comes back after dying, if at all.
+    pub lives: Option<u32>,
+    /// Every object picked up, and where it was, to put back when going back in time.
+    pub picked_up: ChangeLog<((i32, i32), Block)>,
+    /// Every tile changed by dropping loot or using an item, and what was there before, to put
+    /// back when going back in time.
+    pub altered: ChangeLog<((i32, i32), Option<Block>)>,
+    /// The tiles under loot that has been dropped, left behind once it is picked up.
+    pub ground: HashMap<(i32, i32), Block>,
+    /// What the objects on the map do when used.
+    pub items: Items,
+    /// Whether the player is in a boat.
+    pub sailing: bool,
+    /// The creatures moving about the map.
+    pub creatures: Vec<Creature>,
+    /// What creatures move at random with, seeded from the recording so replays match.
+    pub rng: ChaCha8Rng,
+    /// How many turns the player has taken.
+    pub turns: u32,
+    /// How much of the map the quest has been told is explored, in percent.
+    pub explored: u32,
+    /// How things were at the last checkpoint reached, or at the start, to come back to after
+    /// dying.
+    pub checkpoint: Option<Box<Snapshot>>,
 }
 
 pub trait Movement {
@@ -22,15 +80,459 @@ pub trait Movement {
     fn perform_move(&mut self, game: &mut Game, direction: Direction);
 }
 
+impl Player {
+    /// Puts a player at the spawn point of `world`, with the breath its rules allow.
+    pub fn new(world: World, quest: Box<dyn CloneQuest<Event>>) -> Self {
+        let (x, y) = world.meta.spawn();
+        let breath = world.meta.rules.breath;
+        let health = world.meta.rules.health.unwrap_or(DEFAULT_HEALTH);
+        let mut player = Player {
+            x,
+            y,
+            map: world.map,
+            palette: Palette::new(world.palette),
+            npcs: world.npcs,
+            conversation: None,
+            breath,
+            max_breath: breath,
+            health,
+            max_health: health,
+            attack: world.meta.rules.attack.unwrap_or(DEFAULT_ATTACK),
+            defense: world.meta.rules.defense.unwrap_or(0),
+            died: false,
+            quest,
+            won: false,
+            inventory: BTreeMap::new(),
+            log: MessageLog::default(),
+            diagonal: world.meta.rules.diagonal,
+            milestones: 0,
+            lives: world.meta.rules.lives,
+            picked_up: ChangeLog::default(),
+            altered: ChangeLog::default(),
+            ground: HashMap::new(),
+            items: world.items,
+            sailing: false,
+            creatures: world.creatures,
+            rng: ChaCha8Rng::seed_from_u64(0),
+            turns: 0,
+            explored: 0,
+            checkpoint: None,
+        };
+        player.checkpoint = Some(Box::new(Snapshot::checkpoint(&player, 0)));
+        player
+    }
+
+    /// Moves in `direction`, or attacks the creature there, and then gives the creatures their
+    /// turn. A player who dies comes back at the last checkpoint if they have a life left.
+    /// Walking into an NPC talks to them instead, which takes no turn. Returns the positions
+    /// that need drawing again, or `None` if the way is blocked and no turn was taken.
+    pub fn take_turn(&mut self, game: &mut Game, direction: Direction) -> Option<Vec<(i32, i32)>> {
+        // Walking off ends any conversation.
+        self.conversation = None;
+        let from = (self.x, self.y);
+        let ahead = direction.step(from);
+        if let Some(Block::Npc(name)) = self.map.get(&ahead) {
+            let name = name.clone();
+            self.talk_to(game, &name);
+            return Some(vec![]);
+        }
+        let (mut changed, fought) = match self.creature_at(ahead) {
+            Some(index) => {
+                let standing = self.creatures.len();
+                self.fight(game, index);
+                (
+                    vec![ahead],
+                    (self.creatures.len() == standing).then_some(index),
+                )
+            }
+            None => {
+                self.move_player(game, direction);
+                if from == (self.x, self.y) && !self.died {
+                    return None;
+                }
+                (vec![from], None)
+            }
+        };
+        self.turns += 1;
+        if !self.died && !self.won {
+            changed.extend(self.move_creatures(game, fought));
+        }
+        self.reach_checkpoint(game);
+        if self.died {
+            changed.extend(self.respawn(game));
+        }
+        Some(changed)
+    }
+
+    /// Remembers how things are, if the player just stepped onto a checkpoint. Only a
+    /// different checkpoint from the last one is announced.
+    fn reach_checkpoint(&mut self, game: &mut Game) {
+        let position = (self.x, self.y);
+        if self.map.get(&position) != Some(&Block::Checkpoint) || self.died {
+            return;
+        }
+        let moved = self
+            .checkpoint
+            .as_ref()
+            .is_none_or(|checkpoint| checkpoint.position() != position);
+        self.checkpoint = Some(Box::new(Snapshot::checkpoint(self, 0)));
+        if moved {
+            self.say(game, String::from("Checkpoint reached"));
+        }
+    }
+
+    /// Brings a player who died back to the last checkpoint, if they have a life left.
+    /// Returns the positions that need drawing again.
+    fn respawn(&mut self, game: &mut Game) -> Vec<(i32, i32)> {
+        let (lives, checkpoint) = match (self.lives, &self.checkpoint) {
+            (Some(lives), Some(checkpoint)) if lives > 0 => (lives - 1, checkpoint.clone()),
+            _ => return vec![],
+        };
+        self.lives = Some(lives);
+        let changed = (*checkpoint).clone().restore(self);
+        // Checkpoints don't keep one of their own, so this one stays for the next death.
+        self.checkpoint = Some(checkpoint);
+        let text = match lives {
+            1 => String::from("Back to the last checkpoint, 1 life left"),
+            _ => format!("Back to the last checkpoint, {} lives left", lives),
+        };
+        self.say(game, text);
+        changed
+    }
+
+    /// Gives every creature whose turn it is a move, and faces whatever they did. The one at
+    /// `fought` has just hit back, and sits this turn out. Returns the positions creatures
+    /// moved from.
+    fn move_creatures(&mut self, game: &mut Game, fought: Option<usize>) -> Vec<(i32, i32)> {
+        let player = (self.x, self.y);
+        let mut creatures = std::mem::take(&mut self.creatures);
+        let mut rng = self.rng.clone();
+        let mut acts = vec![];
+        for i in 0..creatures.len() {
+            if Some(i) == fought || !self.turns.is_multiple_of(creatures[i].speed.max(1)) {
+                continue;
+            }
+            let (before, after) = creatures.split_at_mut(i);
+            let (creature, after) = after.split_first_mut().unwrap();
+            let taken = |p| before.iter().chain(after.iter()).any(|c| c.at == p);
+            let free = |p| self.roamable(p) && !taken(p);
+            let act = creature.act(player, free, &mut rng);
+            acts.push((act, creature.name.clone(), creature.attack));
+        }
+        self.creatures = creatures;
+        self.rng = rng;
+        let mut changed = vec![];
+        for (act, name, attack) in acts {
+            match act {
+                Act::Stay => {}
+                Act::Moved(from) => changed.push(from),
+                Act::Attack if !self.died => self.hurt(game, attack, &name),
+                Act::Attack => {}
+                Act::LostTrack => {
+                    self.log.push(format!("Got away from the {}", name));
+                    self.register_event(game, &Event::Avoided(name));
+                }
+            }
+        }
+        changed
+    }
+
+    /// Starts a conversation with the NPC called `name`, which counts as talking to them.
+    fn talk_to(&mut self, game: &mut Game, name: &str) {
+        self.log.push(format!("Talked to {}", name));
+        self.register_event(game, &Event::Talked(name.to_string()));
+        if self.won {
+            return;
+        }
+        self.conversation = Conversation::start(&self.npcs, name);
+        match self.conversation {
+            Some(_) => self.log_line(),
+            None => self.say(game, format!("{} has nothing to say", name)),
+        }
+    }
+
+    /// Keeps what the NPC is saying in the log. Showing it is up to the game, which knows the
+    /// keys to answer with.
+    fn log_line(&mut self) {
+        if let Some(conversation) = &self.conversation {
+            if let Some(line) = conversation.line(&self.npcs) {
+                self.log
+                    .push(format!("{}: {}", conversation.npc, line.text));
+            }
+        }
+    }
+
+    /// Answers the NPC with choice `index`, counting from 0, which tells the quest
+    /// `Event::Chose(event)` if the choice has one. Returns whether there was such a choice.
+    pub fn choose(&mut self, game: &mut Game, index: usize) -> bool {
+        let chosen = match &mut self.conversation {
+            Some(conversation) => conversation.choose(&self.npcs, index),
+            None => None,
+        };
+        let (choice, goes_on) = match chosen {
+            Some(chosen) => chosen,
+            None => return false,
+        };
+        self.log.push(format!("You: {}", choice.text));
+        if let Some(event) = choice.event {
+            self.register_event(game, &Event::Chose(event));
+        }
+        if goes_on && !self.won {
+            self.log_line();
+        } else {
+            self.conversation = None;
+        }
+        true
+    }
+
+    /// Shows `text` to the player, and keeps it in the log.
+    pub fn say(&mut self, game: &mut Game, text: String) {
+        self.log.push(text.clone());
+        game.set_message(Some(Message::new(text)));
+    }
+
+    /// Tells the quest about `event`, and wins the game if that completes it. Finishing part
+    /// of the quest is noted in the log.
+    pub fn register_event(&mut self, game: &mut Game, event: &Event) {
+        let before = self.quest.completed_parts();
+        self.quest.register_event(event);
+        if self.quest.get_status() == QuestStatus::Complete {
+            self.won = true;
+            self.say(game, String::from("You Won :)"));
+        } else if self.quest.completed_parts() > before {
+            self.milestones += 1;
+            self.log.push(format!("Quest: {}", self.quest.summary()));
+        }
+    }
+
+    /// Whether the tile at `position` can be walked on.
+    pub fn walkable(&self, position: (i32, i32)) -> bool {
+        match self.map.get(&position) {
+            Some(Block::Barrier | Block::Npc(_)) => false,
+            Some(Block::Custom(name)) => self.palette.get(name).is_none_or(|tile| tile.walkable),
+            _ => true,
+        }
+    }
+
+    /// How stepping on `position` affects breath, or `None` if it can't be walked on.
+    pub fn breath_effect(&self, position: (i32, i32)) -> Option<BreathEffect> {
+        match self.map.get(&position) {
+            _ if !self.walkable(position) => None,
+            Some(Block::Water) if self.sailing => Some(BreathEffect::Hold),
+            Some(Block::Water) => Some(BreathEffect::Drain),
+            Some(Block::Sign(_)) => Some(BreathEffect::Hold),
+            Some(Block::Custom(name)) => Some(
+                self.palette
+                    .get(name)
+                    .map_or(BreathEffect::Refill, |tile| tile.breath),
+            ),
+            _ => Some(BreathEffect::Refill),
+        }
+    }
+
+    /// Whether running in `direction` has to stop before the next step: because the step
+    /// can't be taken, or would go into water.
+    pub fn run_blocked(&self, direction: Direction) -> bool {
+        let from = (self.x, self.y);
+        let effect = self.breath_effect(direction.step(from));
+        matches!(effect, None | Some(BreathEffect::Drain))
+            || !can_step(self.diagonal, from, direction, |p| self.walkable(p))
+    }
+
+    /// Whether running has to stop after the last step: the game is over, the player stands
+    /// on a sign or an object, something was shown, or part of the quest was finished since
+    /// `milestones`.
+    pub fn run_over(&self, game: &Game, milestones: usize) -> bool {
+        self.died
+            || self.won
+            || self.milestones > milestones
+            || game.get_message().is_some()
+            || matches!(
+                self.map.get(&(self.x, self.y)),
+                Some(Block::Sign(_) | Block::Object(_))
+            )
+    }
+
+    /// The creature at `position`, if there is one.
+    pub fn creature_at(&self, position: (i32, i32)) -> Option<usize> {
+        self.creatures.iter().position(|c| c.at == position)
+    }
+
+    /// Whether a creature could stand at `position`: on a tile of dry land, other than a
+    /// sign, object or checkpoint, with nobody else there.
+    pub fn roamable(&self, position: (i32, i32)) -> bool {
+        let land = match self.map.get(&position) {
+            None | Some(Block::Water | Block::Sign(_) | Block::Object(_) | Block::Checkpoint) => {
+                false
+            }
+            Some(_) => matches!(
+                self.breath_effect(position),
+                Some(BreathEffect::Refill | BreathEffect::Hold)
+            ),
+        };
+        land && self.creature_at(position).is_none()
+    }
+
+    /// Attacks the creature at `index`, which is defeated once it runs out of health and drops
+    /// its loot. One that is still standing hits back.
+    pub fn fight(&mut self, game: &mut Game, index: usize) {
+        let creature = &mut self.creatures[index];
+        let dealt = damage(self.attack, creature.defense);
+        creature.health -= dealt;
+        if creature.health > 0 {
+            let (attack, name) = (creature.attack, creature.name.clone());
+            let mut text = format!(
+                "You hit the {} for {}, {} health left",
+                name, dealt, creature.health
+            );
+            self.log.push(text.clone());
+            if attack > 0 {
+                let taken = self.take_hit(attack, &name);
+                self.log.push(taken.clone());
+                text = format!("{}\n{}", text, taken);
+            }
+            return game.set_message(Some(Message::new(text)));
+        }
+        let creature = self.creatures.remove(index);
+        let text = match creature.loot {
+            Some(loot) => {
+                match self.map.get(&creature.at) {
+                    Some(Block::Object(_)) | None => {}
+                    Some(ground) => {
+                        self.ground.insert(creature.at, ground.clone());
+                    }
+                }
+                self.alter(creature.at, Block::Object(loot));
+                format!(
+                    "You defeated the {}, it dropped a '{}'",
+                    creature.name, loot
+                )
+            }
+            None => format!("You defeated the {}", creature.name),
+        };
+        self.say(game, text);
+        self.register_event(game, &Event::Defeated(creature.name));
+    }
+
+    /// Takes a hit with `attack` from the creature called `name`, dying if it is too much.
+    fn hurt(&mut self, game: &mut Game, attack: i32, name: &str) {
+        let text = self.take_hit(attack, name);
+        self.say(game, text);
+    }
+
+    /// Loses the health a hit with `attack` does, and tells how it went.
+    fn take_hit(&mut self, attack: i32, name: &str) -> String {
+        let taken = damage(attack, self.defense);
+        self.health -= taken;
+        if self.health <= 0 {
+            self.died = true;
+            format!("You were defeated by the {} :(", name)
+        } else {
+            format!("The {} hits you for {}", name, taken)
+        }
+    }
+
+    /// Uses the item `glyph` from the bag, which tells the quest `Event::Used(name)`. Returns
+    /// the positions that need drawing again, or None if nothing was used.
+    pub fn use_item(&mut self, game: &mut Game, glyph: char) -> Option<Vec<(i32, i32)>> {
+        let item = match items::find(&self.items, glyph) {
+            Some(item) if self.inventory.contains_key(&glyph) => item.clone(),
+            _ => return None,
+        };
+        let mut changed = vec![];
+        let text = match item.effect {
+            Effect::Breath(breath) => {
+                self.breath = (self.breath + breath).min(self.max_breath);
+                format!(
+                    "You use the {}, breath {}/{}",
+                    item.name, self.breath, self.max_breath
+                )
+            }
+            Effect::Heal(health) => {
+                self.health = (self.health + health).min(self.max_health);
+                format!(
+                    "You use the {}, health {}/{}",
+                    item.name, self.health, self.max_health
+                )
+            }
+            Effect::Boat
+                if self.sailing && self.map.get(&(self.x, self.y)) == Some(&Block::Water) =>
+            {
+                self.say(game, String::from("There is nowhere to get out here"));
+                return None;
+            }
+            Effect::Boat => {
+                self.sailing = !self.sailing;
+                match self.sailing {
+                    true => format!("You get in the {}", item.name),
+                    false => format!("You get out of the {}", item.name),
+                }
+            }
+            Effect::Mine => {
+                let here = (self.x, self.y);
+                for next in Direction::CARDINAL.iter().map(|d| d.step(here)) {
+                    if self.map.get(&next) == Some(&Block::Rock) {
+                        self.alter(next, Block::Sand);
+                        changed.push(next);
+                    }
+                }
+                match changed.is_empty() {
+                    true => format!("There is no rock to break with the {}", item.name),
+                    false => format!("You break the rock with the {}", item.name),
+                }
+            }
+        };
+        if item.effect.used_up() {
+            match self.inventory.get_mut(&glyph) {
+                Some(count) if *count > 1 => *count -= 1,
+                _ => {
+                    self.inventory.remove(&glyph);
+                }
+            }
+        }
+        self.say(game, text);
+        self.register_event(game, &Event::Used(item.name));
+        Some(changed)
+    }
+
+    /// Puts `block` at `position`, remembering what was there.
+    fn alter(&mut self, position: (i32, i32), block: Block) {
+        let before = self.map.insert(position, block);
+        self.altered.push((position, before));
+    }
+
+    fn hold_breath(&mut self, game: &mut Game, direction: Direction) {
+        self.breath -= 1;
+        if self.breath <= 0 {
+            self.died = true;
+            self.say(game, String::from("You Drowned :("));
+        } else {
+            self.perform_move(game, direction)
+        }
+    }
+}
+
 impl Movement for Player {
     fn remove_player(&mut self, game: &mut Game) {
         if let Some(b) = self.map.get(&(self.x, self.y)) {
             match b {
                 Block::Object(_) => {
-                    game.set_screen_char(self.x, self.y, Some(StyledCharacter::from(' ')));
-                    self.map.remove(&(self.x, self.y));
+                    let here = (self.x, self.y);
+                    let object = match self.ground.remove(&here) {
+                        Some(ground) => {
+                            game.set_screen_char(self.x, self.y, Some(self.palette.style(&ground)));
+                            self.map.insert(here, ground)
+                        }
+                        None => {
+                            game.set_screen_char(self.x, self.y, Some(StyledCharacter::from(' ')));
+                            self.map.remove(&here)
+                        }
+                    };
+                    if let Some(object) = object {
+                        self.picked_up.push((here, object));
+                    }
                 }
-                _ => game.set_screen_char(self.x, self.y, Some(StyledCharacter::from(b))),
+                _ => game.set_screen_char(self.x, self.y, Some(self.palette.style(b))),
             }
         } else {
             game.set_screen_char(self.x, self.y, None);
@@ -42,98 +544,265 @@ impl Movement for Player {
         } else {
             game.set_screen_char(self.x, self.y, Some(StyledCharacter::from('♟')));
         }
-
-        let viewport = game.get_viewport();
-        if self.x > viewport.x + 76 {
-            game.set_viewport(ViewportLocation {
-                x: self.x - 76,
-                y: viewport.y,
-            });
-        }
-        if self.x < viewport.x {
-            game.set_viewport(ViewportLocation {
-                x: self.x,
-                y: viewport.y,
-            });
-        }
-        if self.y > viewport.y + 20 {
-            game.set_viewport(ViewportLocation {
-                x: viewport.x,
-                y: self.y - 20,
-            });
-        }
-        if self.y < viewport.y {
-            game.set_viewport(ViewportLocation {
-                x: viewport.x,
-                y: self.y,
-            });
-        }
     }
     fn move_player(&mut self, game: &mut Game, direction: Direction) {
+        let from = (self.x, self.y);
+        if !can_step(self.diagonal, from, direction, |p| self.walkable(p)) {
+            return;
+        }
+        let sailing = self.sailing;
         let nb = self.check_move(&direction);
         match nb {
             Some(b) => match b {
-                Block::Barrier => {}
+                Block::Barrier | Block::Npc(_) => {}
                 Block::Sign(message) => {
-                    let m = Message::new(String::from(message));
-                    game.set_message(Some(m));
+                    let message = message.clone();
+                    self.say(game, message);
                     self.perform_move(game, direction)
                 }
-                Block::Water => {
-                    self.breath -= 1;
-                    if self.breath == 0 {
-                        self.died = true;
-                        game.set_message(Some(Message::new(String::from("You Drowned :("))));
-                    } else {
-                        self.perform_move(game, direction)
+                Block::Water if sailing => self.perform_move(game, direction),
+                Block::Water => self.hold_breath(game, direction),
+                Block::Custom(name) => {
+                    let name = name.clone();
+                    let (walkable, breath, message) = match self.palette.get(&name) {
+                        Some(tile) => (tile.walkable, tile.breath, tile.message.clone()),
+                        None => (true, BreathEffect::Refill, None),
+                    };
+                    if !walkable {
+                        return;
+                    }
+                    if let Some(message) = message {
+                        self.say(game, message);
+                    }
+                    match breath {
+                        BreathEffect::Refill => {
+                            self.breath = self.max_breath;
+                            self.perform_move(game, direction)
+                        }
+                        BreathEffect::Hold => self.perform_move(game, direction),
+                        BreathEffect::Drain => self.hold_breath(game, direction),
                     }
                 }
                 _ => {
-                    self.breath = 10;
+                    self.breath = self.max_breath;
                     self.perform_move(game, direction)
                 }
             },
             None => {
-                self.breath = 10;
+                self.breath = self.max_breath;
                 self.perform_move(game, direction)
             }
         }
     }
     fn check_move(&mut self, direction: &Direction) -> Option<&Block> {
-        match direction {
-            Direction::Up => self.map.get(&(self.x, self.y - 1)),
-            Direction::Down => self.map.get(&(self.x, self.y + 1)),
-            Direction::Left => self.map.get(&(self.x - 1, self.y)),
-            Direction::Right => self.map.get(&(self.x + 1, self.y)),
-        }
+        self.map.get(&direction.step((self.x, self.y)))
     }
 
     fn perform_move(&mut self, game: &mut Game, direction: Direction) {
-        let block = match direction {
-            Direction::Up => self.map.get(&(self.x, self.y - 1)),
-            Direction::Down => self.map.get(&(self.x, self.y + 1)),
-            Direction::Left => self.map.get(&(self.x - 1, self.y)),
-            Direction::Right => self.map.get(&(self.x + 1, self.y)),
-        };
-        match block {
-            Some(_) => {
-                self.quest.register_event(block.unwrap());
-            }
-            None => {
-                self.quest.register_event(&Block::Empty);
-            }
+        let block = self.map.get(&direction.step((self.x, self.y)));
+        let event = block.cloned().unwrap_or(Block::Empty);
+        if let Block::Object(c) = event {
+            *self.inventory.entry(c).or_default() += 1;
+            self.log.push(format!("Picked up '{}'", c));
         }
         self.remove_player(game);
-        match direction {
-            Direction::Up => self.y -= 1,
-            Direction::Down => self.y += 1,
-            Direction::Left => self.x -= 1,
-            Direction::Right => self.x += 1,
-        }
+        (self.x, self.y) = direction.step((self.x, self.y));
         self.show_player(game);
-        if self.quest.get_status() == QuestStatus::Complete {
-            self.won = true;
-            game.set_message(Some(Message::new(String::from("You Won :)"))));
+        self.register_event(game, &Event::Tile(event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creatures::Creature;
+    use crate::direction::Direction;
+    use crate::event::Event;
+    use crate::map::MapFile;
+    use crate::player::{Movement, Player};
+    use crate::replay::{simulate, Step};
+    use crate::undo::Snapshot;
+    use crate::Block;
+    use adventurers_quest::{OrderedQuest, SubQuest};
+    use termgame::{CharChunkMap, Game};
+
+    /// Runs like `MyGame::run`, returning where the player stopped.
+    fn run(player: &mut Player, direction: Direction) -> (i32, i32) {
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+        let milestones = player.milestones;
+        while !player.run_blocked(direction) {
+            player.move_player(&mut game, direction);
+            if player.run_over(&game, milestones) {
+                break;
+            }
         }
+        (player.x, player.y)
+    }
+
+    #[test]
+    fn runs_until_something_happens() {
+        let map = MapFile::parse(
+            "{ (2, 2): Grass, (3, 2): Grass, (4, 2): Object('x'), (5, 2): Grass, (6, 2): Grass,
+               (7, 2): Water, (6, 3): Sand, (6, 4): Sign(\"Hi\"), (6, 5): Barrier }",
+        )
+        .unwrap();
+        let quest = OrderedQuest::new(vec![
+            SubQuest::new(Event::Tile(Block::Grass), 2, "Walk on grass".to_string()),
+            SubQuest::new(Event::Tile(Block::Sand), 5, "Walk on sand".to_string()),
+        ]);
+        let mut player = Player::new(map.into(), Box::new(quest));
+        assert_eq!((4, 2), run(&mut player, Direction::Right));
+        assert_eq!(Some(&1), player.inventory.get(&'x'));
+        // The second step onto grass finishes the first part of the quest.
+        assert_eq!((5, 2), run(&mut player, Direction::Right));
+        assert_eq!((6, 2), run(&mut player, Direction::Right));
+        assert_eq!((6, 4), run(&mut player, Direction::Down));
+        assert_eq!((6, 4), run(&mut player, Direction::Down));
+    }
+
+    #[test]
+    fn creatures_take_turns_with_the_seed() {
+        let tiles = (0..10)
+            .flat_map(|x| (0..10).map(move |y| format!("({}, {}): Grass", x, y)))
+            .collect::<Vec<_>>();
+        let map = MapFile::parse(&format!("{{ {} }}", tiles.join(", "))).unwrap();
+        let quest = SubQuest::new(Event::Tile(Block::Sand), 1, "Walk on Sand".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let rabbit = "(name: \"Rabbit\", at: (5, 5))";
+        player
+            .creatures
+            .push(ron::from_str::<Creature>(rabbit).unwrap());
+        let moves = [
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::Up,
+        ];
+
+        let mut again = player.clone();
+        simulate(&mut player, &moves);
+        simulate(&mut again, &moves);
+        assert_eq!(4, player.turns);
+        assert_eq!(player.creatures[0].at, again.creatures[0].at);
+
+        // Walking into a barrier isn't a turn, so the rabbit stays put.
+        let at = player.creatures[0].at;
+        let blocked = Direction::CARDINAL
+            .into_iter()
+            .find(|d| d.step((player.x, player.y)) != at)
+            .unwrap();
+        player
+            .map
+            .insert(blocked.step((player.x, player.y)), Block::Barrier);
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+        assert_eq!(None, player.take_turn(&mut game, blocked));
+        assert_eq!((4, at), (player.turns, player.creatures[0].at));
+    }
+
+    #[test]
+    fn fights_until_a_creature_drops_its_loot() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Sand }").unwrap();
+        let quest = SubQuest::new(
+            Event::Defeated("Wolf".to_string()),
+            2,
+            "Defeat 2 wolves".to_string(),
+        );
+        let mut player = Player::new(map.into(), Box::new(quest));
+        player.attack = 2;
+        let wolf =
+            "(name: \"Wolf\", at: (3, 2), attack: 3, defense: 1, health: 2, loot: Some('f'))";
+        player
+            .creatures
+            .push(ron::from_str::<Creature>(wolf).unwrap());
+        let before = Snapshot::take(&player, 0);
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+
+        player.fight(&mut game, 0);
+        assert_eq!(1, player.creatures[0].health);
+        assert_eq!(7, player.health);
+        player.fight(&mut game, 0);
+        assert!(player.creatures.is_empty());
+        assert_eq!(7, player.health);
+        assert_eq!(Some(&Block::Object('f')), player.map.get(&(3, 2)));
+        assert_eq!("Defeat 2 wolves (1/2)", player.quest.summary());
+
+        // The loot is picked up on the way over it, leaving the sand it was dropped on.
+        let looted = Snapshot::take(&player, 0);
+        simulate(&mut player, &[Direction::Right, Direction::Left]);
+        assert_eq!(Some(&1), player.inventory.get(&'f'));
+        assert_eq!(Some(&Block::Sand), player.map.get(&(3, 2)));
+        looted.restore(&mut player);
+        assert_eq!(Some(&Block::Object('f')), player.map.get(&(3, 2)));
+        simulate(&mut player, &[Direction::Right, Direction::Left]);
+        assert_eq!(Some(&Block::Sand), player.map.get(&(3, 2)));
+
+        before.restore(&mut player);
+        assert_eq!(Some(&Block::Sand), player.map.get(&(3, 2)));
+        assert_eq!(2, player.creatures[0].health);
+    }
+
+    #[test]
+    fn talking_and_choosing_are_steps() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Npc(\"Hermit\") }").unwrap();
+        let quest = OrderedQuest::new(vec![
+            SubQuest::new(Event::Talked("Hermit".to_string()), 1, "Talk".to_string()),
+            SubQuest::new(Event::Chose("help".to_string()), 1, "Help".to_string()),
+        ]);
+        let mut player = Player::new(map.into(), Box::new(quest));
+        player.npcs = ron::from_str(include_str!("../../maps/village.npcs.ron")).unwrap();
+        let talk = Step::Move(Direction::Right);
+        simulate(&mut player, &[talk, Step::Choose(0)]);
+        assert_eq!((2, 2), (player.x, player.y));
+        assert!(player.conversation.is_some());
+        let before = Snapshot::take(&player, 0);
+        let mut walked_off = player.clone();
+        simulate(&mut player, &[Step::Choose(0)]);
+        assert!(player.won);
+        assert!(player.conversation.is_none());
+        before.restore(&mut player);
+        assert!(player.conversation.is_some());
+
+        simulate(
+            &mut walked_off,
+            &[Step::Move(Direction::Left), Step::Choose(0)],
+        );
+        assert!(walked_off.conversation.is_none());
+        assert!(!walked_off.won);
+    }
+
+    #[test]
+    fn items_do_what_they_are_for() {
+        let mut source = String::from("{ (2, 2): Grass, (2, 1): Rock, ");
+        for x in 3..16 {
+            source.push_str(&format!("({}, 2): Water, ", x));
+        }
+        source.push_str("(16, 2): Sand }");
+        let map = MapFile::parse(&source).unwrap();
+        let quest = SubQuest::new(Event::Used("boat".to_string()), 2, "Sail".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        player.items = ron::from_str(include_str!("../../maps/village.items.ron")).unwrap();
+        player
+            .inventory
+            .extend([('a', 2), ('b', 1), ('p', 1), ('x', 1)]);
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+
+        assert_eq!(Some(vec![(2, 1)]), player.use_item(&mut game, 'p'));
+        assert_eq!(Some(&Block::Sand), player.map.get(&(2, 1)));
+        assert_eq!(None, player.use_item(&mut game, 'x'));
+
+        player.use_item(&mut game, 'b');
+        simulate(&mut player, &[Direction::Right; 14]);
+        assert_eq!(((16, 2), false), ((player.x, player.y), player.died));
+        assert_eq!("Sail (1/2)", player.quest.summary());
+
+        player.sailing = false;
+        player.breath = 3;
+        player.use_item(&mut game, 'a');
+        assert_eq!(8, player.breath);
+        assert_eq!(Some(&1), player.inventory.get(&'a'));
+        assert_eq!(Some(&1), player.inventory.get(&'p'));
     }
 }
@@ -0,0 +1,72 @@
+use crate::direction::{Corners, Direction};
+use crate::player::Player;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use termgame::{CharChunkMap, Game};
+
+/// A game saved by `play --save` or `solve --save`, to be watched again with `replay`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// The map played, unless it was generated from `seed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub quest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn: Option<(i32, i32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sight: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagonal: Option<Corners>,
+    /// What creatures move at random with, see [`Player::rng`].
+    #[serde(default)]
+    pub creature_seed: u64,
+    pub steps: Vec<Step>,
+}
+
+/// Something the player did, as recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    Move(Direction),
+    /// Used the item with this glyph.
+    Use(char),
+    /// Answered the NPC being talked to with this choice, counting from 0.
+    Choose(usize),
+}
+
+impl From<Direction> for Step {
+    fn from(direction: Direction) -> Self {
+        Step::Move(direction)
+    }
+}
+
+impl Recording {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, PrettyConfig::default())
+    }
+}
+
+/// Takes `steps` without a terminal, following the same rules as the game. Moves can be
+/// given as plain directions. Stops early if the player dies or wins.
+pub fn simulate<S: Copy + Into<Step>>(player: &mut Player, steps: &[S]) {
+    let mut screen = CharChunkMap::new();
+    let mut game = Game::new(&mut screen);
+    for step in steps {
+        if player.died || player.won {
+            break;
+        }
+        match (*step).into() {
+            Step::Move(direction) => {
+                player.take_turn(&mut game, direction);
+            }
+            Step::Use(glyph) => {
+                player.use_item(&mut game, glyph);
+            }
+            Step::Choose(index) => {
+                player.choose(&mut game, index);
+            }
+        }
+    }
+}
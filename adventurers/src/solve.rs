@@ -0,0 +1,135 @@
+use crate::direction::{directions, Direction};
+use crate::map::Bounds;
+use crate::player::Player;
+use std::collections::{HashSet, VecDeque};
+use termgame::{CharChunkMap, Game};
+
+/// Gives up after this many moves.
+const MAX_MOVES: usize = 10_000;
+
+/// A position, the breath and health the player has left there, and the health of every
+/// creature, so that hitting one again counts as getting somewhere.
+type State = ((i32, i32), i32, i32, Vec<i32>);
+
+/// Looks for moves that win the game for `player`, by walking to whichever tile nearby makes
+/// progress on the quest, over and over. The player never leaves the map or dies on the way.
+///
+/// Every step is tried on a copy of the player, so the rules are the game's own: breath,
+/// fights and creatures moving with the player's seed all play out as they would when the
+/// moves are played. The search is greedy, so it can miss solutions that need progress to be
+/// made in a particular order.
+pub fn solve(player: &Player) -> Option<Vec<Direction>> {
+    let bounds = player.map.bounds()?;
+    let mut screen = CharChunkMap::new();
+    let mut game = Game::new(&mut screen);
+    let mut player = player.clone();
+    let mut moves = vec![];
+    // Where the player has been, and how the quest looked at the time.
+    let mut seen = HashSet::new();
+    loop {
+        if player.won {
+            return Some(moves);
+        }
+        let progress = player.quest.to_string();
+        seen.insert(((player.x, player.y), progress.clone()));
+        let (path, reached) = nearest_progress(&mut game, &player, bounds, &seen)?;
+        moves.extend(path);
+        player = reached;
+        if moves.len() > MAX_MOVES {
+            return None;
+        }
+    }
+}
+
+/// The shortest path the player survives that moves the quest on, to a state it hasn't been
+/// in at the end of the path before, and how the player ends up after it.
+fn nearest_progress(
+    game: &mut Game,
+    player: &Player,
+    bounds: Bounds,
+    seen: &HashSet<((i32, i32), String)>,
+) -> Option<(Vec<Direction>, Player)> {
+    let progress = player.quest.to_string();
+    let state = |p: &Player| -> State {
+        let creatures = p.creatures.iter().map(|c| c.health).collect();
+        ((p.x, p.y), p.breath, p.health, creatures)
+    };
+    // The start isn't marked as reached, so that a path can come back to where it started.
+    let mut reached = HashSet::new();
+    let mut queue = VecDeque::from([(player.clone(), vec![])]);
+    while let Some((from, path)) = queue.pop_front() {
+        for direction in directions(from.diagonal) {
+            let mut next = from.clone();
+            if next.take_turn(game, *direction).is_none()
+                || next.died
+                || next.lives != from.lives
+                || !bounds.contains((next.x, next.y))
+                || !reached.insert(state(&next))
+            {
+                continue;
+            }
+            let mut path = path.clone();
+            path.push(*direction);
+            let changed = next.quest.to_string();
+            if next.won || (changed != progress && !seen.contains(&((next.x, next.y), changed))) {
+                return Some((path, next));
+            }
+            queue.push_back((next, path));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::creatures::Creature;
+    use crate::event::Event;
+    use crate::map::MapFile;
+    use crate::player::Player;
+    use crate::replay::simulate;
+    use crate::solve::solve;
+    use adventurers_quest::{CloneQuest, OrderedQuest, SubQuest};
+
+    fn quest() -> Box<dyn CloneQuest<Event>> {
+        Box::new(OrderedQuest::new(vec![
+            SubQuest::new(
+                Event::Tile(Block::Object('x')),
+                2,
+                "Collect a 'x'".to_string(),
+            ),
+            SubQuest::new(Event::Tile(Block::Sand), 3, "Walk on Sand".to_string()),
+        ]))
+    }
+
+    #[test]
+    fn solution_wins_the_game() {
+        let map = MapFile::parse(include_str!("../../maps/testing_game.ron")).unwrap();
+        let mut player = Player::new(map.into(), quest());
+        let moves = solve(&player).unwrap();
+        simulate(&mut player, &moves);
+        assert!(player.won);
+        assert!(!player.died);
+    }
+
+    #[test]
+    fn nothing_to_walk_to() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Barrier, (4, 2): Sand }").unwrap();
+        assert_eq!(None, solve(&Player::new(map.into(), quest())));
+    }
+
+    #[test]
+    fn fights_its_way_through() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Grass, (4, 2): Sand }").unwrap();
+        let quest = SubQuest::new(Event::Tile(Block::Sand), 1, "Walk on Sand".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let guard = "(name: \"Guard\", at: (3, 2), behaviour: Patrol([]), health: 3)";
+        player
+            .creatures
+            .push(ron::from_str::<Creature>(guard).unwrap());
+        let moves = solve(&player).unwrap();
+        assert_eq!(5, moves.len());
+        simulate(&mut player, &moves);
+        assert!(player.won);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::block::Block;
+use crate::map::{MapError, MapFile, MapMeta};
+use crate::palette::TileType;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Tiled stores flip/rotation flags in the top bits of a tile id.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+/// Says which [`Block`] each Tiled tile id stands for.
+///
+/// Ids are the global ids found in the layer data, so the first tile of the first
+/// tileset is `1`. The palette lets the table refer to `Custom` blocks.
+///
+/// ```text
+/// (
+///     palette: [(name: "Lava", glyph: '^', bg: Some(Red), breath: Drain)],
+///     tiles: { 1: Grass, 2: Sand, 3: Water, 4: Barrier, 5: Custom("Lava") },
+/// )
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct TileTable {
+    #[serde(default)]
+    pub palette: Vec<TileType>,
+    pub tiles: HashMap<u32, Block>,
+}
+
+impl Default for TileTable {
+    /// Numbers the built-in terrains in the order they are declared in [`Block`].
+    fn default() -> Self {
+        let tiles = [
+            Block::Grass,
+            Block::Sand,
+            Block::Rock,
+            Block::Cinderblock,
+            Block::Flowerbush,
+            Block::Barrier,
+            Block::Water,
+        ];
+        TileTable {
+            palette: vec![],
+            tiles: (1..).zip(tiles).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TiledMap {
+    tilewidth: f64,
+    tileheight: f64,
+    layers: Vec<Layer>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Layer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        #[serde(default)]
+        data: Option<Vec<u32>>,
+        #[serde(default)]
+        chunks: Vec<Chunk>,
+        #[serde(default)]
+        encoding: Option<String>,
+        #[serde(default)]
+        x: i32,
+        #[serde(default)]
+        y: i32,
+        width: i32,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects { objects: Vec<Object> },
+    #[serde(rename = "group")]
+    Group { layers: Vec<Layer> },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    data: Vec<u32>,
+    x: i32,
+    y: i32,
+    width: i32,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    #[serde(default)]
+    name: String,
+    /// Called `type` before Tiled 1.9, and `class` after.
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+struct Property {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn property<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|p| p.name == name)
+        .and_then(|p| p.value.as_str())
+}
+
+/// Imports a map exported from Tiled as JSON.
+///
+/// Tile layers are stacked in order, each tile going through `table`. In object layers,
+/// objects of type `sign` become a [`Block::Sign`] with their `text` property (or name),
+/// objects of type `object` become a [`Block::Object`] with their `char` property (or the
+/// first letter of their name), and an object of type `spawn` sets the spawn point.
+/// Tile objects without a type are looked up in `table` like any other tile.
+///
+/// The `title`, `author` and `quest` custom properties of the map fill in its header.
+pub fn import(source: &str, table: TileTable) -> Result<MapFile, MapError> {
+    let tiled: TiledMap = serde_json::from_str(source).map_err(MapError::Json)?;
+    let mut importer = Importer {
+        table: &table,
+        tile_width: tiled.tilewidth,
+        tile_height: tiled.tileheight,
+        tiles: HashMap::new(),
+        spawn: None,
+    };
+    importer.layers(&tiled.layers)?;
+    let Importer { tiles, spawn, .. } = importer;
+    let text = |name| property(&tiled.properties, name).map(String::from);
+    let meta = MapMeta {
+        title: text("title"),
+        author: text("author"),
+        spawn,
+        quest: text("quest"),
+        ..MapMeta::default()
+    };
+    Ok(MapFile {
+        meta,
+        palette: table.palette,
+        tiles,
+    })
+}
+
+struct Importer<'a> {
+    table: &'a TileTable,
+    tile_width: f64,
+    tile_height: f64,
+    tiles: HashMap<(i32, i32), Block>,
+    spawn: Option<(i32, i32)>,
+}
+
+impl<'a> Importer<'a> {
+    fn layers(&mut self, layers: &[Layer]) -> Result<(), MapError> {
+        for layer in layers {
+            match layer {
+                Layer::Tiles {
+                    encoding: Some(encoding),
+                    ..
+                } if encoding != "csv" => {
+                    return Err(MapError::Tiled(format!(
+                        "tile layers encoded as {} are not supported, export them as CSV",
+                        encoding
+                    )));
+                }
+                Layer::Tiles {
+                    data,
+                    chunks,
+                    x,
+                    y,
+                    width,
+                    ..
+                } => {
+                    if let Some(data) = data {
+                        self.tile_data(data, *x, *y, *width)?;
+                    }
+                    for chunk in chunks {
+                        self.tile_data(&chunk.data, chunk.x, chunk.y, chunk.width)?;
+                    }
+                }
+                Layer::Objects { objects } => {
+                    for object in objects {
+                        self.object(object)?;
+                    }
+                }
+                Layer::Group { layers } => self.layers(layers)?,
+                Layer::Other => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn tile_data(&mut self, data: &[u32], x: i32, y: i32, width: i32) -> Result<(), MapError> {
+        if width <= 0 {
+            return Err(MapError::Tiled(format!(
+                "tile data at ({}, {}) has width {}, but needs to be at least 1 wide",
+                x, y, width
+            )));
+        }
+        for (i, gid) in data.iter().enumerate() {
+            let position = (x + i as i32 % width, y + i as i32 / width);
+            self.tile(*gid, position)?;
+        }
+        Ok(())
+    }
+
+    fn tile(&mut self, gid: u32, (x, y): (i32, i32)) -> Result<(), MapError> {
+        let id = gid & !FLIP_FLAGS;
+        if id == 0 {
+            return Ok(());
+        }
+        match self.table.tiles.get(&id) {
+            Some(block) => {
+                self.tiles.insert((x, y), block.clone());
+                Ok(())
+            }
+            None => Err(MapError::UnknownTileId { id, x, y }),
+        }
+    }
+
+    fn object(&mut self, object: &Object) -> Result<(), MapError> {
+        // Tile objects are anchored at their bottom-left corner, everything else at the top-left.
+        let y = match object.gid {
+            Some(_) => object.y - self.tile_height,
+            None => object.y,
+        };
+        let position = (
+            (object.x / self.tile_width).floor() as i32,
+            (y / self.tile_height).floor() as i32,
+        );
+        let kind = match object.class.as_str() {
+            "" => object.kind.as_str(),
+            class => class,
+        };
+        match (kind, object.gid) {
+            ("sign", _) => {
+                let text = property(&object.properties, "text").unwrap_or(&object.name);
+                self.tiles.insert(position, Block::Sign(text.to_string()));
+            }
+            ("object", _) => {
+                let name = property(&object.properties, "char").unwrap_or(&object.name);
+                match name.chars().next() {
+                    Some(c) => {
+                        self.tiles.insert(position, Block::Object(c));
+                    }
+                    None => {
+                        return Err(MapError::Tiled(format!(
+                            "object at {:?} needs a name or a `char` property",
+                            position
+                        )))
+                    }
+                }
+            }
+            ("spawn", _) => self.spawn = Some(position),
+            ("", Some(gid)) => self.tile(gid, position)?,
+            (kind, _) => {
+                return Err(MapError::Tiled(format!(
+                    "object at {:?} has unknown type '{}', expected sign, object or spawn",
+                    position, kind
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::tiled::{import, TileTable};
+
+    const MAP: &str = r#"{
+        "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+        "layers": [
+            { "type": "tilelayer", "name": "ground", "x": 0, "y": 0, "width": 3, "height": 2,
+              "data": [6, 1, 2, 6, 7, 2147483655] },
+            { "type": "objectgroup", "name": "things", "objects": [
+                { "id": 1, "type": "sign", "x": 16, "y": 0, "properties": [
+                    { "name": "text", "type": "string", "value": "Welcome!" }
+                ] },
+                { "id": 2, "class": "object", "name": "x", "x": 40, "y": 4 },
+                { "id": 3, "class": "spawn", "x": 20, "y": 20 }
+            ] }
+        ]
+    }"#;
+
+    #[test]
+    fn tiles_and_objects() {
+        let map = import(MAP, TileTable::default()).unwrap();
+        assert_eq!(Some(&Block::Barrier), map.tiles.get(&(0, 0)));
+        assert_eq!(
+            Some(&Block::Sign("Welcome!".to_string())),
+            map.tiles.get(&(1, 0))
+        );
+        assert_eq!(Some(&Block::Object('x')), map.tiles.get(&(2, 0)));
+        assert_eq!(Some(&Block::Water), map.tiles.get(&(1, 1)));
+        assert_eq!(Some(&Block::Water), map.tiles.get(&(2, 1)));
+        assert_eq!(Some((1, 1)), map.meta.spawn);
+    }
+
+    #[test]
+    fn no_width() {
+        let map = MAP.replace(r#""y": 0, "width": 3"#, r#""y": 0, "width": 0"#);
+        let err = import(&map, TileTable::default()).unwrap_err();
+        assert_eq!(
+            "tile data at (0, 0) has width 0, but needs to be at least 1 wide",
+            err.to_string()
+        );
+    }
+}
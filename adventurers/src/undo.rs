@@ -0,0 +1,409 @@
+use crate::creatures::Creature;
+use crate::dialogue::Conversation;
+use crate::event::Event;
+use crate::player::Player;
+use crate::replay::Step;
+use adventurers_quest::CloneQuest;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, VecDeque};
+
+/// How many steps can be undone, unless the map or `--undo` says otherwise.
+pub const DEFAULT_DEPTH: usize = 100;
+
+/// Everything a move can change, to go back to.
+#[derive(Clone)]
+pub struct Snapshot {
+    position: (i32, i32),
+    breath: i32,
+    health: i32,
+    died: bool,
+    quest: Box<dyn CloneQuest<Event>>,
+    inventory: BTreeMap<char, u32>,
+    milestones: usize,
+    /// How many objects had been picked up.
+    picked_up: usize,
+    /// How many tiles had been altered.
+    altered: usize,
+    sailing: bool,
+    creatures: Vec<Creature>,
+    conversation: Option<Conversation>,
+    rng: ChaCha8Rng,
+    turns: u32,
+    explored: u32,
+    /// The checkpoint the player would have come back to, which checkpoints don't keep.
+    checkpoint: Option<Box<Snapshot>>,
+    /// How many steps had been recorded.
+    pub steps: usize,
+}
+
+impl Snapshot {
+    pub fn take(player: &Player, steps: usize) -> Self {
+        Snapshot {
+            checkpoint: player.checkpoint.clone(),
+            ..Snapshot::checkpoint(player, steps)
+        }
+    }
+
+    /// Like [`Snapshot::take`], for a checkpoint to come back to. It doesn't keep the
+    /// checkpoint before it, so that checkpoints don't hold on to every earlier one.
+    pub fn checkpoint(player: &Player, steps: usize) -> Self {
+        let position = (player.x, player.y);
+        Snapshot {
+            position,
+            breath: player.breath,
+            health: player.health,
+            died: player.died,
+            quest: player.quest.clone(),
+            inventory: player.inventory.clone(),
+            milestones: player.milestones,
+            picked_up: player.picked_up.made(),
+            altered: player.altered.made(),
+            sailing: player.sailing,
+            creatures: player.creatures.clone(),
+            conversation: player.conversation.clone(),
+            rng: player.rng.clone(),
+            turns: player.turns,
+            explored: player.explored,
+            checkpoint: None,
+            steps,
+        }
+    }
+
+    /// Puts `player` back the way they were. Returns the positions that need drawing again.
+    pub fn restore(self, player: &mut Player) -> Vec<(i32, i32)> {
+        let mut changed = vec![(player.x, player.y), self.position];
+        (player.x, player.y) = self.position;
+        player.breath = self.breath;
+        player.health = self.health;
+        player.died = self.died;
+        player.quest = self.quest;
+        player.inventory = self.inventory;
+        player.milestones = self.milestones;
+        changed.extend(player.creatures.iter().map(|c| c.at));
+        changed.extend(self.creatures.iter().map(|c| c.at));
+        player.creatures = self.creatures;
+        player.conversation = self.conversation;
+        player.rng = self.rng;
+        player.turns = self.turns;
+        player.explored = self.explored;
+        player.checkpoint = self.checkpoint;
+        for (position, object) in player.picked_up.undo_to(self.picked_up) {
+            if let Some(ground) = player.map.insert(position, object) {
+                player.ground.insert(position, ground);
+            }
+            changed.push(position);
+        }
+        player.sailing = self.sailing;
+        for (position, before) in player.altered.undo_to(self.altered) {
+            player.ground.remove(&position);
+            match before {
+                Some(block) => player.map.insert(position, block),
+                None => player.map.remove(&position),
+            };
+            changed.push(position);
+        }
+        changed
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        self.position
+    }
+}
+
+/// Changes to the map in the order they were made, to take back when going back to a
+/// [`Snapshot`]. Changes older than any snapshot still kept can be forgotten.
+#[derive(Debug, Clone)]
+pub struct ChangeLog<T> {
+    changes: VecDeque<T>,
+    /// How many changes were forgotten.
+    forgotten: usize,
+}
+
+impl<T> Default for ChangeLog<T> {
+    fn default() -> Self {
+        ChangeLog {
+            changes: VecDeque::new(),
+            forgotten: 0,
+        }
+    }
+}
+
+impl<T> ChangeLog<T> {
+    pub fn push(&mut self, change: T) {
+        self.changes.push_back(change);
+    }
+
+    /// How many changes were ever made, counting forgotten ones.
+    pub fn made(&self) -> usize {
+        self.forgotten + self.changes.len()
+    }
+
+    /// How many changes are still remembered.
+    pub fn remembered(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Takes back the changes made since there were `made`, newest first.
+    pub fn undo_to(&mut self, made: usize) -> Vec<T> {
+        let keep = made.saturating_sub(self.forgotten).min(self.changes.len());
+        self.changes.drain(keep..).rev().collect()
+    }
+
+    /// Forgets the changes made before there were `made`.
+    pub fn forget_before(&mut self, made: usize) {
+        let forget = made.saturating_sub(self.forgotten).min(self.changes.len());
+        self.changes.drain(..forget);
+        self.forgotten += forget;
+    }
+}
+
+/// The steps that can be undone, and the ones undone that can be taken again.
+pub struct History {
+    depth: usize,
+    undo: VecDeque<(Snapshot, Step)>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Remembers up to `depth` steps, or none at all with a depth of 0.
+    pub fn new(depth: usize) -> Self {
+        History {
+            depth,
+            undo: VecDeque::new(),
+            redo: vec![],
+        }
+    }
+
+    /// Remembers how things were `before` taking `step`. Taking a step other than the next one
+    /// to redo means the undone steps can't be redone anymore.
+    pub fn record(&mut self, before: Snapshot, step: Step) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back((before, step));
+        if self.redo.last() == Some(&step) {
+            self.redo.pop();
+        } else {
+            self.redo.clear();
+        }
+    }
+
+    /// How things were before the last step, which can then be redone.
+    pub fn undo(&mut self) -> Option<Snapshot> {
+        let (before, step) = self.undo.pop_back()?;
+        self.redo.push(step);
+        Some(before)
+    }
+
+    /// The last step undone, which `record` crosses off once it is taken again.
+    pub fn redo(&self) -> Option<Step> {
+        self.redo.last().copied()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Forgets every step, after which there is nothing to undo or redo.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Forgets the changes to the map that neither the oldest step that can be undone nor the
+    /// player's checkpoint could need to go back past, so they don't pile up.
+    pub fn forget_changes(&self, player: &mut Player) {
+        let kept = self
+            .undo
+            .front()
+            .map(|(before, _)| before)
+            .into_iter()
+            .chain(player.checkpoint.as_deref());
+        let picked_up = kept.clone().map(|s| s.picked_up).min();
+        let altered = kept.map(|s| s.altered).min();
+        player
+            .picked_up
+            .forget_before(picked_up.unwrap_or(player.picked_up.made()));
+        player
+            .altered
+            .forget_before(altered.unwrap_or(player.altered.made()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::direction::Direction;
+    use crate::event::Event;
+    use crate::map::MapFile;
+    use crate::player::Player;
+    use crate::replay::{simulate, Step};
+    use crate::undo::{History, Snapshot};
+    use adventurers_quest::SubQuest;
+
+    #[test]
+    fn undo_and_redo_moves() {
+        let map = MapFile::parse("{ (2, 2): Grass, (3, 2): Object('x'), (3, 3): Water }").unwrap();
+        let quest = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            2,
+            "Collect a 'x'".to_string(),
+        );
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let mut history = History::new(2);
+        for direction in [Direction::Right, Direction::Down] {
+            history.record(Snapshot::take(&player, 0), direction.into());
+            simulate(&mut player, &[direction]);
+        }
+        assert_eq!(None, player.map.get(&(3, 2)));
+        assert_eq!(9, player.breath);
+
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!((3, 2, 10), (player.x, player.y, player.breath));
+        assert_eq!(Some(&Block::Object('x')), player.map.get(&(3, 2)));
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!((2, 2), (player.x, player.y));
+        assert!(player.inventory.is_empty());
+        assert_eq!("Collect a 'x' (0/2)", player.quest.summary());
+        assert!(!history.can_undo());
+
+        assert_eq!(Some(Step::Move(Direction::Right)), history.redo());
+        history.record(Snapshot::take(&player, 0), Step::Move(Direction::Right));
+        assert_eq!(Some(Step::Move(Direction::Down)), history.redo());
+        history.record(Snapshot::take(&player, 0), Step::Move(Direction::Left));
+        assert_eq!(None, history.redo());
+    }
+
+    #[test]
+    fn undo_item_use() {
+        let map = MapFile::parse("{ (2, 2): Grass, (2, 1): Rock, (3, 2): Grass }").unwrap();
+        let quest = SubQuest::new(Event::Used("pickaxe".to_string()), 2, "Mine".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        player.items = ron::from_str(include_str!("../../maps/village.items.ron")).unwrap();
+        player.inventory.insert('p', 1);
+        let mut history = History::new(2);
+        let steps = [Step::Use('p'), Step::Move(Direction::Right)];
+        for step in steps {
+            history.record(Snapshot::take(&player, 0), step);
+            simulate(&mut player, &[step]);
+        }
+        assert_eq!(Some(&Block::Sand), player.map.get(&(2, 1)));
+        assert_eq!((3, 2), (player.x, player.y));
+
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!((2, 2), (player.x, player.y));
+        assert_eq!(Some(&Block::Sand), player.map.get(&(2, 1)));
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!(Some(&Block::Rock), player.map.get(&(2, 1)));
+        assert_eq!("Mine (0/2)", player.quest.summary());
+        assert_eq!(Some(Step::Use('p')), history.redo());
+    }
+
+    #[test]
+    fn checkpoints_put_objects_back() {
+        let map =
+            MapFile::parse("{ (2, 2): Checkpoint, (3, 2): Object('x'), (4, 2): Object('y') }")
+                .unwrap();
+        let quest = SubQuest::new(
+            Event::Tile(Block::Object('x')),
+            2,
+            "Collect a 'x'".to_string(),
+        );
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let checkpoint = Snapshot::take(&player, 0);
+        for _ in 0..2 {
+            simulate(
+                &mut player,
+                &[Direction::Right, Direction::Right, Direction::Right],
+            );
+            assert_eq!(2, player.picked_up.made());
+            let changed = checkpoint.clone().restore(&mut player);
+            assert!(changed.contains(&(4, 2)));
+            assert_eq!(Some(&Block::Object('x')), player.map.get(&(3, 2)));
+            assert_eq!(Some(&Block::Object('y')), player.map.get(&(4, 2)));
+            assert!(player.inventory.is_empty());
+        }
+    }
+
+    #[test]
+    fn dying_goes_back_to_the_checkpoint() {
+        let map = MapFile::parse(
+            "(meta: (rules: (breath: 2, lives: 1)), \
+             tiles: { (2, 2): Grass, (3, 2): Checkpoint, (4, 2): Water, (5, 2): Water })",
+        )
+        .unwrap();
+        let quest = SubQuest::new(Event::Tile(Block::Sand), 1, "Walk on Sand".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let swim = [Direction::Right, Direction::Right, Direction::Right];
+        simulate(&mut player, &swim);
+        assert_eq!((3, 2), (player.x, player.y));
+        assert_eq!(
+            (Some(0), false, 2),
+            (player.lives, player.died, player.breath)
+        );
+        simulate(&mut player, &swim);
+        assert!(player.died);
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_checkpoint_before() {
+        let map = MapFile::parse(
+            "(meta: (rules: (breath: 1, lives: 1)), tiles: { (2, 2): Grass, \
+             (3, 2): Object('x'), (4, 2): Checkpoint, (2, 3): Water, (2, 4): Water })",
+        )
+        .unwrap();
+        let quest = SubQuest::new(Event::Tile(Block::Sand), 1, "Walk on Sand".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let mut history = History::new(2);
+        for direction in [Direction::Right, Direction::Right] {
+            history.record(Snapshot::take(&player, 0), direction.into());
+            simulate(&mut player, &[direction]);
+        }
+        assert_eq!(
+            Some((4, 2)),
+            player.checkpoint.as_ref().map(|c| c.position())
+        );
+        history.undo().unwrap().restore(&mut player);
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!(
+            Some((2, 2)),
+            player.checkpoint.as_ref().map(|c| c.position())
+        );
+
+        simulate(&mut player, &[Direction::Down, Direction::Down]);
+        assert_eq!((2, 2), (player.x, player.y));
+        assert!(player.inventory.is_empty());
+        assert_eq!(Some(&Block::Object('x')), player.map.get(&(3, 2)));
+        assert_eq!(
+            Some((2, 2)),
+            player.checkpoint.as_ref().map(|c| c.position())
+        );
+    }
+
+    #[test]
+    fn old_changes_are_forgotten() {
+        let tiles = (3..8)
+            .map(|x| format!("({}, 2): Object('x')", x))
+            .collect::<Vec<_>>();
+        let map =
+            MapFile::parse(&format!("{{ (2, 2): Checkpoint, {} }}", tiles.join(", "))).unwrap();
+        let quest = SubQuest::new(Event::Tile(Block::Sand), 1, "Walk on Sand".to_string());
+        let mut player = Player::new(map.into(), Box::new(quest));
+        let mut history = History::new(1);
+        // Leave the checkpoint behind, so that only the last move needs its changes.
+        player.checkpoint = None;
+        for _ in 0..5 {
+            history.record(Snapshot::take(&player, 0), Step::Move(Direction::Right));
+            simulate(&mut player, &[Direction::Right]);
+            history.forget_changes(&mut player);
+        }
+        assert_eq!(4, player.picked_up.made());
+        assert_eq!(1, player.picked_up.remembered());
+        history.undo().unwrap().restore(&mut player);
+        assert_eq!(Some(&Block::Object('x')), player.map.get(&(6, 2)));
+        assert_eq!((6, 2), (player.x, player.y));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::block::Block;
+use crate::dialogue::Npcs;
+use crate::map::MapFile;
+use crate::palette::BreathEffect;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Something wrong with a map, found by [`validate`].
+#[derive(Debug)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(message: String) -> Self {
+        Problem {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Problem {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+/// Checks a map that parsed successfully for mistakes that would spoil the game:
+/// rules that can't be played by, unknown custom tiles, NPCs with nothing or no line to say,
+/// a bad spawn point, and parts of the map that can't be reached. `npcs` is what the map's
+/// NPCs say.
+pub fn validate(map: &MapFile, npcs: &Npcs) -> Vec<Problem> {
+    if map.tiles.is_empty() {
+        return vec![Problem::error(String::from("the map has no tiles"))];
+    }
+    let mut problems = vec![];
+    if let Err(err) = map.meta.rules.check() {
+        problems.push(Problem::error(err));
+    }
+    check_palette(map, &mut problems);
+    check_npcs(map, npcs, &mut problems);
+    check_spawn(map, &mut problems);
+    check_reachable(map, &mut problems);
+    problems
+}
+
+fn check_palette(map: &MapFile, problems: &mut Vec<Problem>) {
+    let mut names = HashSet::new();
+    for tile in &map.palette {
+        if !names.insert(&tile.name) {
+            problems.push(Problem::error(format!(
+                "custom tile \"{}\" is declared more than once in the palette",
+                tile.name
+            )));
+        }
+    }
+
+    let mut unknown: BTreeMap<&String, Vec<(i32, i32)>> = BTreeMap::new();
+    for (position, block) in &map.tiles {
+        if let Block::Custom(name) = block {
+            if !names.contains(name) {
+                unknown.entry(name).or_default().push(*position);
+            }
+        }
+    }
+    for (name, mut positions) in unknown {
+        positions.sort();
+        problems.push(Problem::error(format!(
+            "unknown custom tile \"{}\" is used {} time(s), first at {:?}",
+            name,
+            positions.len(),
+            positions[0]
+        )));
+    }
+}
+
+fn check_npcs(map: &MapFile, npcs: &Npcs, problems: &mut Vec<Problem>) {
+    let mut unknown: BTreeMap<&String, Vec<(i32, i32)>> = BTreeMap::new();
+    for (position, block) in &map.tiles {
+        if let Block::Npc(name) = block {
+            if !npcs.contains_key(name) {
+                unknown.entry(name).or_default().push(*position);
+            }
+        }
+    }
+    for (name, mut positions) in unknown {
+        positions.sort();
+        problems.push(Problem::error(format!(
+            "unknown NPC \"{}\" is used {} time(s), first at {:?}",
+            name,
+            positions.len(),
+            positions[0]
+        )));
+    }
+
+    let sorted: BTreeMap<_, _> = npcs.iter().collect();
+    for (name, npc) in sorted {
+        if !npc.lines.contains_key(&npc.start) {
+            problems.push(Problem::error(format!(
+                "NPC \"{}\" starts with line \"{}\", which doesn't exist",
+                name, npc.start
+            )));
+        }
+        let lines: BTreeMap<_, _> = npc.lines.iter().collect();
+        for (key, line) in lines {
+            for next in line.choices.iter().filter_map(|c| c.next.as_ref()) {
+                if !npc.lines.contains_key(next) {
+                    problems.push(Problem::error(format!(
+                        "line \"{}\" of NPC \"{}\" leads to line \"{}\", which doesn't exist",
+                        key, name, next
+                    )));
+                }
+            }
+        }
+    }
+}
+
+fn check_spawn(map: &MapFile, problems: &mut Vec<Problem>) {
+    let spawn = map.meta.spawn();
+    let custom = |name: &String| map.palette.iter().find(|t| &t.name == name);
+    match map.tiles.get(&spawn) {
+        None if !map.bounds().is_some_and(|b| b.contains(spawn)) => problems.push(Problem::error(
+            format!("spawn point {:?} is outside the map", spawn),
+        )),
+        Some(Block::Barrier) => problems.push(Problem::error(format!(
+            "spawn point {:?} is on a barrier",
+            spawn
+        ))),
+        Some(Block::Npc(name)) => problems.push(Problem::error(format!(
+            "spawn point {:?} is on {}",
+            spawn, name
+        ))),
+        Some(Block::Custom(name)) if custom(name).is_some_and(|t| !t.walkable) => {
+            problems.push(Problem::error(format!(
+                "spawn point {:?} is on \"{}\", which can't be walked on",
+                spawn, name
+            )))
+        }
+        Some(Block::Water) => problems.push(Problem::warning(format!(
+            "spawn point {:?} is in water",
+            spawn
+        ))),
+        Some(Block::Object(c)) => problems.push(Problem::warning(format!(
+            "spawn point {:?} is on object '{}', which can never be picked up",
+            spawn, c
+        ))),
+        _ => {}
+    }
+}
+
+fn check_reachable(map: &MapFile, problems: &mut Vec<Problem>) {
+    let reachable = survivable(map);
+    let mut stranded: HashSet<(i32, i32)> = HashSet::new();
+    if let Some(bounds) = map.bounds() {
+        for x in bounds.min.0..=bounds.max.0 {
+            for y in bounds.min.1..=bounds.max.1 {
+                if !reachable.contains(&(x, y)) && map.breath_effect((x, y)).is_some() {
+                    stranded.insert((x, y));
+                }
+            }
+        }
+    }
+
+    let mut starts: Vec<(i32, i32)> = stranded.iter().copied().collect();
+    starts.sort();
+    for start in starts {
+        if !stranded.remove(&start) {
+            continue;
+        }
+        let mut region = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for next in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)] {
+                if stranded.remove(&next) {
+                    region.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        let objects = region
+            .iter()
+            .filter(|p| matches!(map.tiles.get(p), Some(Block::Object(_))))
+            .count();
+        let signs = region
+            .iter()
+            .filter(|p| matches!(map.tiles.get(p), Some(Block::Sign(_))))
+            .count();
+        let message = format!(
+            "{} tile(s) starting at {:?} can't be reached from the spawn point",
+            region.len(),
+            start
+        );
+        if objects > 0 || signs > 0 {
+            problems.push(Problem::error(format!(
+                "{}, including {} object(s) and {} sign(s)",
+                message, objects, signs
+            )));
+        } else {
+            problems.push(Problem::warning(message));
+        }
+    }
+}
+
+/// Every tile the player can get to from the spawn point without drowning.
+fn survivable(map: &MapFile) -> HashSet<(i32, i32)> {
+    let bounds = match map.bounds() {
+        Some(bounds) => bounds,
+        None => return HashSet::new(),
+    };
+    let max_breath = map.meta.rules.breath;
+    let spawn = map.meta.spawn();
+    // The most breath the player can have left on arriving at each tile.
+    let mut best: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut queue = VecDeque::new();
+    if bounds.contains(spawn) {
+        best.insert(spawn, max_breath);
+        queue.push_back((spawn, max_breath));
+    }
+    while let Some((position, breath)) = queue.pop_front() {
+        if best[&position] > breath {
+            continue;
+        }
+        for (_, next) in map.steps(position) {
+            if !bounds.contains(next) {
+                continue;
+            }
+            let left = match map.breath_effect(next) {
+                None => continue,
+                Some(BreathEffect::Refill) => max_breath,
+                Some(BreathEffect::Hold) => breath,
+                Some(BreathEffect::Drain) => breath - 1,
+            };
+            if left > 0 && best.get(&next).is_none_or(|b| *b < left) {
+                best.insert(next, left);
+                queue.push_back((next, left));
+            }
+        }
+    }
+    best.into_keys().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dialogue::Npcs;
+    use crate::map::MapFile;
+    use crate::validate::{validate, Severity};
+
+    fn errors(source: &str) -> Vec<String> {
+        npc_errors(source, "{}")
+    }
+
+    fn npc_errors(source: &str, npcs: &str) -> Vec<String> {
+        let npcs: Npcs = ron::from_str(npcs).unwrap();
+        validate(&MapFile::parse(source).unwrap(), &npcs)
+            .into_iter()
+            .filter(|p| p.severity == Severity::Error)
+            .map(|p| p.message)
+            .collect()
+    }
+
+    #[test]
+    fn shipped_maps_are_valid() {
+        assert!(errors(include_str!("../../maps/full_game.ron")).is_empty());
+        assert!(errors(include_str!("../../maps/custom_tiles.ron")).is_empty());
+        assert!(npc_errors(
+            include_str!("../../maps/village.ron"),
+            include_str!("../../maps/village.npcs.ron")
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn bad_spawn() {
+        let errors = errors("{ (2, 2): Barrier, (2, 3): Grass }");
+        assert_eq!(vec!["spawn point (2, 2) is on a barrier"], errors);
+    }
+
+    #[test]
+    fn unknown_custom_tile() {
+        let errors = errors("{ (2, 2): Grass, (2, 3): Custom(\"Lava\") }");
+        assert_eq!(
+            vec!["unknown custom tile \"Lava\" is used 1 time(s), first at (2, 3)"],
+            errors
+        );
+    }
+
+    #[test]
+    fn npc_lines_missing() {
+        let npcs = "{ \"Guard\": (start: \"hello\", lines: { \"hi\": (text: \"Hi\", \
+                    choices: [(text: \"Bye\"), (text: \"What?\", next: Some(\"what\"))]) }) }";
+        let errors = npc_errors(
+            "{ (2, 2): Grass, (2, 3): Npc(\"Guard\"), (2, 4): Npc(\"Hermit\") }",
+            npcs,
+        );
+        assert_eq!(
+            vec![
+                "unknown NPC \"Hermit\" is used 1 time(s), first at (2, 4)",
+                "NPC \"Guard\" starts with line \"hello\", which doesn't exist",
+                "line \"hi\" of NPC \"Guard\" leads to line \"what\", which doesn't exist",
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn no_breath() {
+        let errors = errors("(meta: (rules: (breath: 0)), tiles: { (2, 2): Grass })");
+        assert_eq!(vec!["breath is 0, but has to be at least 1"], errors);
+    }
+
+    #[test]
+    fn object_across_deep_water() {
+        let mut source = String::from("{ (2, 2): Grass,");
+        for x in 3..13 {
+            source.push_str(&format!("({}, 2): Water,", x));
+        }
+        source.push_str("(13, 2): Object('x') }");
+        assert_eq!(
+            vec!["2 tile(s) starting at (12, 2) can't be reached from the spawn point, including 1 object(s) and 0 sign(s)"],
+            errors(&source)
+        );
+    }
+}
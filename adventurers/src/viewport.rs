@@ -0,0 +1,156 @@
+use crate::chunks::ChunkedMap;
+use crate::map::{Bounds, MapError};
+use termgame::{Game, StyledCharacter, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// How many tiles beyond the edges of the screen are drawn ahead of time, so the screen
+/// doesn't have to be redrawn on every step.
+const MARGIN: i32 = 16;
+
+/// Rows kept free at the bottom of the screen for the status bar.
+pub const STATUS_ROWS: i32 = 1;
+
+/// How many tiles fit on screen, inside the border and above the status bar and any message.
+/// At least one, even when a tall message leaves no room.
+pub fn view_size(game: &Game) -> (i32, i32) {
+    let (width, (height, _)) = game.screen_size();
+    (
+        (width as i32 - 2).max(1),
+        (height as i32 - 2 - STATUS_ROWS).max(1),
+    )
+}
+
+/// The tiles on screen.
+pub fn visible(game: &Game) -> Bounds {
+    let viewport = game.get_viewport();
+    let (width, height) = view_size(game);
+    Bounds {
+        min: (viewport.x, viewport.y),
+        max: (viewport.x + width - 1, viewport.y + height - 1),
+    }
+}
+
+/// The tile under a cell of a terminal `terminal` columns and rows big, if it is on screen.
+///
+/// The game is drawn in the middle of the terminal, with a border around the map.
+pub fn tile_at(game: &Game, terminal: (u16, u16), (column, row): (u16, u16)) -> Option<(i32, i32)> {
+    let left = terminal.0.saturating_sub(SCREEN_WIDTH) / 2 + 1;
+    let top = terminal.1.saturating_sub(SCREEN_HEIGHT) / 2 + 1;
+    let visible = visible(game);
+    let tile = (
+        visible.min.0 + column as i32 - left as i32,
+        visible.min.1 + row as i32 - top as i32,
+    );
+    visible.contains(tile).then_some(tile)
+}
+
+/// Draws the part of a map around the viewport, and nothing else.
+///
+/// The tiles on screen and a margin around them are drawn together. Scrolling within the
+/// margin costs nothing; once the screen gets past it, tiles that are now too far away are
+/// cleared and the new ones drawn.
+#[derive(Default)]
+pub struct Renderer {
+    drawn: Option<Bounds>,
+}
+
+impl Renderer {
+    /// Brings the screen up to date with the viewport, loading any chunks that came into view.
+    /// `style` decides how each position is drawn.
+    pub fn update(
+        &mut self,
+        game: &mut Game,
+        map: &mut ChunkedMap,
+        style: impl Fn(&ChunkedMap, (i32, i32)) -> Option<StyledCharacter>,
+    ) -> Option<MapError> {
+        let visible = visible(game);
+        if let Some(drawn) = self.drawn {
+            if drawn.contains(visible.min) && drawn.contains(visible.max) {
+                return None;
+            }
+        }
+        let area = Bounds {
+            min: (visible.min.0 - MARGIN, visible.min.1 - MARGIN),
+            max: (visible.max.0 + MARGIN, visible.max.1 + MARGIN),
+        };
+        let error = map.load(area);
+        if let Some(drawn) = self.drawn {
+            for (x, y) in drawn.positions().filter(|p| !area.contains(*p)) {
+                game.set_screen_char(x, y, None);
+            }
+        }
+        let drawn = self.drawn;
+        for (x, y) in area
+            .positions()
+            .filter(|p| !drawn.is_some_and(|d| d.contains(*p)))
+        {
+            game.set_screen_char(x, y, style(map, (x, y)));
+        }
+        self.drawn = Some(area);
+        error
+    }
+
+    /// Draws `positions` again, if they are near enough to the viewport to be drawn at all.
+    pub fn redraw(
+        &self,
+        game: &mut Game,
+        map: &ChunkedMap,
+        positions: impl IntoIterator<Item = (i32, i32)>,
+        style: impl Fn(&ChunkedMap, (i32, i32)) -> Option<StyledCharacter>,
+    ) {
+        let drawn = match self.drawn {
+            Some(drawn) => drawn,
+            None => return,
+        };
+        for (x, y) in positions.into_iter().filter(|p| drawn.contains(*p)) {
+            game.set_screen_char(x, y, style(map, (x, y)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::Block;
+    use crate::camera::Camera;
+    use crate::chunks::ChunkedMap;
+    use crate::palette::Palette;
+    use crate::viewport::{tile_at, Renderer, MARGIN};
+    use std::collections::HashMap;
+    use termgame::ViewportLocation;
+    use termgame::{CharChunkMap, Game};
+
+    #[test]
+    fn only_draws_near_the_viewport() {
+        let tiles = (0..1000)
+            .map(|x| ((x, 5), Block::Sand))
+            .collect::<HashMap<_, _>>();
+        let mut map = ChunkedMap::new(tiles);
+        let palette = Palette::new(vec![]);
+        let style = |map: &ChunkedMap, p| map.get(&p).map(|block| palette.style(block));
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+        let mut renderer = Renderer::default();
+
+        renderer.update(&mut game, &mut map, style);
+        assert!(game.get_screen_char(0, 5).is_some());
+        assert!(game.get_screen_char(77 + MARGIN, 5).is_some());
+        assert!(game.get_screen_char(78 + MARGIN, 5).is_none());
+
+        Camera::default().follow(&mut game, (500, 5));
+        assert_eq!(500 - 76, game.get_viewport().x);
+        renderer.update(&mut game, &mut map, style);
+        assert!(game.get_screen_char(0, 5).is_none());
+        assert!(game.get_screen_char(500, 5).is_some());
+    }
+
+    #[test]
+    fn clicks_land_on_tiles() {
+        let mut screen = CharChunkMap::new();
+        let mut game = Game::new(&mut screen);
+        game.set_viewport(ViewportLocation { x: 10, y: 20 });
+        assert_eq!(Some((10, 20)), tile_at(&game, (80, 24), (1, 1)));
+        assert_eq!(Some((15, 22)), tile_at(&game, (100, 30), (16, 6)));
+        // The border, and the status bar under the map.
+        assert_eq!(None, tile_at(&game, (80, 24), (0, 5)));
+        assert_eq!(None, tile_at(&game, (80, 24), (5, 22)));
+    }
+}
@@ -13,7 +13,7 @@ pub enum QuestStatus {
     ///Quest is still ongoing
     Ongoing,
 }
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// An enum created for testing purpose.
 /// All quest implements register(event), an event could be a terrain type in the game.
 /// The actual event type used in the game is upto the implementor of the game.
@@ -54,10 +54,48 @@ pub trait Quest<Event>: std::fmt::Display + std::fmt::Debug {
 pub trait QuestExt<Event>: Quest<Event> {
     /// returns the status of the current quest
     fn get_status(&mut self) -> QuestStatus;
+    /// A one line summary of the progress, short enough for a status bar.
+    /// Defaults to the first line of the [`std::fmt::Display`] output.
+    fn summary(&self) -> String {
+        self.to_string().lines().next().unwrap_or("").to_string()
+    }
+    /// How many parts of the quest are done, e.g. steps of an [`OrderedQuest`], so a game can
+    /// tell when the player gets further. Defaults to 0 for quests without parts.
+    fn completed_parts(&self) -> u32 {
+        0
+    }
+}
+
+/// A quest that can be copied with its progress so far, e.g. to go back to later, even
+/// behind a `Box<dyn CloneQuest<Event>>`.
+/// Every quest that implements [`Clone`] gets this for free.
+pub trait CloneQuest<Event>: QuestExt<Event> {
+    /// A boxed copy of the quest and its progress.
+    fn clone_box(&self) -> Box<dyn CloneQuest<Event>>;
+}
+
+impl<Event, T: QuestExt<Event> + Clone + 'static> CloneQuest<Event> for T {
+    fn clone_box(&self) -> Box<dyn CloneQuest<Event>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<Event> Clone for Box<dyn CloneQuest<Event>> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// `prompt (count/target)`, or `prompt ✅` once complete.
+fn count_summary(prompt: &str, count: u32, target_count: u32, complete: QuestStatus) -> String {
+    match complete {
+        QuestStatus::Complete => format!("{} ✅", prompt),
+        QuestStatus::Ongoing => format!("{} ({}/{})", prompt, count, target_count),
+    }
 }
 /// Subquest struct has a target count for a target type, and is
 /// useful for implementing quests of type "The player wins the game if they walk over 5 sand blocks".
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubQuest<Event: std::fmt::Debug + std::cmp::PartialEq> {
     target_type: Event,
     target_count: u32,
@@ -134,12 +172,19 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for SubQuest<
     fn get_status(&mut self) -> QuestStatus {
         self.complete
     }
+    fn summary(&self) -> String {
+        count_summary(&self.prompt, self.count, self.target_count, self.complete)
+    }
+    /// 1 once complete.
+    fn completed_parts(&self) -> u32 {
+        (self.complete == QuestStatus::Complete) as u32
+    }
 }
 /// SubMemQuest struct
 ///
 /// A SubMemQuest stores 2 types of target_count and is
 /// useful for implementing quests of type "walk over 9 blocks of water, 3 times".
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubMemQuest<Event: std::fmt::Debug + std::cmp::PartialEq> {
     target_type: Event,
     target_count: u32,
@@ -235,6 +280,13 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for SubMemQue
     fn get_status(&mut self) -> QuestStatus {
         self.complete
     }
+    fn summary(&self) -> String {
+        count_summary(&self.prompt, self.count, self.target_count, self.complete)
+    }
+    /// 1 once complete.
+    fn completed_parts(&self) -> u32 {
+        (self.complete == QuestStatus::Complete) as u32
+    }
 }
 
 /// OrderedQuest struct
@@ -243,7 +295,7 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for SubMemQue
 ///
 /// Useful for implementing quests of type "First, collect five objects called 'x',
 /// After finishing that, collect three objects called 'y'".
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OrderedQuest<Event: std::fmt::Debug + std::cmp::PartialEq> {
     sub_quests: Vec<SubQuest<Event>>,
     complete: QuestStatus,
@@ -314,6 +366,29 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for OrderedQu
     fn get_status(&mut self) -> QuestStatus {
         self.complete
     }
+    /// The step the player is on, e.g. `Step 2/3: Collect a 'y' (0/3)`.
+    fn summary(&self) -> String {
+        match self
+            .sub_quests
+            .iter()
+            .position(|quest| quest.complete == QuestStatus::Ongoing)
+        {
+            Some(step) => format!(
+                "Step {}/{}: {}",
+                step + 1,
+                self.sub_quests.len(),
+                self.sub_quests[step].summary()
+            ),
+            None => format!("All {} steps ✅", self.sub_quests.len()),
+        }
+    }
+    /// The steps done so far.
+    fn completed_parts(&self) -> u32 {
+        self.sub_quests
+            .iter()
+            .map(|quest| quest.completed_parts())
+            .sum()
+    }
 }
 
 /// QuestSystem struct
@@ -329,7 +404,7 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for OrderedQu
 /// "collect a 'y' object", then "walk on grass"
 ///
 /// "walk over 9 blocks of water, 3 times"
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct QuestSystem<Event: std::fmt::Debug + std::cmp::PartialEq> {
     v_ordered: Vec<OrderedQuest<Event>>,
     v_sub_quest: Vec<SubQuest<Event>>,
@@ -447,11 +522,28 @@ impl<Event: std::fmt::Debug + std::cmp::PartialEq> QuestExt<Event> for QuestSyst
     fn get_status(&mut self) -> QuestStatus {
         self.complete
     }
+    /// How many of the quests are done, e.g. `1/2 quests done`.
+    fn summary(&self) -> String {
+        match self.complete {
+            QuestStatus::Complete => format!("{}/{} quests done ✅", self.count, self.target_count),
+            QuestStatus::Ongoing => format!("{}/{} quests done", self.count, self.target_count),
+        }
+    }
+    /// The parts done across all of the quests.
+    fn completed_parts(&self) -> u32 {
+        let ordered = self.v_ordered.iter().map(|quest| quest.completed_parts());
+        let sub = self.v_sub_quest.iter().map(|quest| quest.completed_parts());
+        let mem = self.v_mem_quest.iter().map(|quest| quest.completed_parts());
+        ordered.chain(sub).chain(mem).sum()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Block, OrderedQuest, Quest, QuestSystem, SubMemQuest, SubQuest};
+    use crate::{
+        Block, CloneQuest, OrderedQuest, Quest, QuestExt, QuestStatus, QuestSystem, SubMemQuest,
+        SubQuest,
+    };
 
     #[test]
     fn sub_quest1() {
@@ -633,4 +725,62 @@ mod tests {
         quest_sys.register_event(&Block::Water);
         assert_eq!("[✅] You must complete at least 2 of these quests...\n  [✅] You must, in order, complete each of these quests:...\n    [✅] Walk on Grass 5 times...\n     ^ (Complete 0 more times)\n    [✅] Walk on Sand...\n  \n  [ ] You must, in order, complete each of these quests:...\n    [ ] Walk on Grass 6 times...\n     ^ (Complete 1 more times)\n    [ ] Walk on Sand...\n  \n  [✅] Walk over exactly 5 blocks of water...\n   ^ (Complete 0 more times)\n", quest_sys.to_string());
     }
+
+    #[test]
+    fn summaries() {
+        let test_quest1 = SubQuest::new(Block::Grass, 2, "Walk on Grass".to_string());
+        let test_quest2 = SubQuest::new(Block::Sand, 1, "Walk on Sand".to_string());
+        let mut ordered = OrderedQuest::new(vec![test_quest1, test_quest2]);
+        ordered.register_event(&Block::Grass);
+        assert_eq!("Step 1/2: Walk on Grass (1/2)", ordered.summary());
+        ordered.register_event(&Block::Grass);
+        assert_eq!("Step 2/2: Walk on Sand (0/1)", ordered.summary());
+        ordered.register_event(&Block::Sand);
+        assert_eq!("All 2 steps ✅", ordered.summary());
+
+        let mut quest_sys = QuestSystem::new(
+            vec![],
+            vec![SubQuest::new(Block::Rock, 1, "Climb a rock".to_string())],
+            vec![],
+            1,
+        );
+        assert_eq!("0/1 quests done", quest_sys.summary());
+        quest_sys.register_event(&Block::Rock);
+        assert_eq!("1/1 quests done ✅", quest_sys.summary());
+    }
+
+    #[test]
+    fn clones_keep_their_own_progress() {
+        let mut quest: Box<dyn CloneQuest<Block>> = Box::new(OrderedQuest::new(vec![
+            SubQuest::new(Block::Grass, 1, "Walk on Grass".to_string()),
+            SubQuest::new(Block::Sand, 1, "Walk on Sand".to_string()),
+        ]));
+        quest.register_event(&Block::Grass);
+        let mut snapshot = quest.clone();
+        quest.register_event(&Block::Sand);
+        assert_eq!(QuestStatus::Complete, quest.get_status());
+        assert_eq!(QuestStatus::Ongoing, snapshot.get_status());
+        assert_eq!("Step 2/2: Walk on Sand (0/1)", snapshot.summary());
+    }
+
+    #[test]
+    fn completed_parts() {
+        let mut ordered = OrderedQuest::new(vec![
+            SubQuest::new(Block::Grass, 2, "Walk on Grass".to_string()),
+            SubQuest::new(Block::Sand, 1, "Walk on Sand".to_string()),
+        ]);
+        ordered.register_event(&Block::Grass);
+        assert_eq!(0, ordered.completed_parts());
+        ordered.register_event(&Block::Grass);
+        assert_eq!(1, ordered.completed_parts());
+
+        let mut quest_sys = QuestSystem::new(
+            vec![ordered],
+            vec![SubQuest::new(Block::Rock, 1, "Climb a rock".to_string())],
+            vec![SubMemQuest::new(Block::Water, 2, 1, "Swim".to_string())],
+            3,
+        );
+        quest_sys.register_event(&Block::Rock);
+        assert_eq!(2, quest_sys.completed_parts());
+    }
 }